@@ -0,0 +1,187 @@
+// This is free and unencumbered software released into the public domain.
+
+use asimov_linkup_module::{Client, TokenStoreError, cli};
+use asimov_module::SysexitsError::{self, *};
+use clientele::{
+    StandardOptions,
+    crates::clap::{self, Parser, Subcommand, ValueEnum},
+};
+use jiff::{SignedDuration, Timestamp};
+use std::io::Write;
+
+#[cfg(not(feature = "std"))]
+fn main() {
+    unimplemented!("asimov-linkup-auth requires the 'std' feature")
+}
+
+/// ASIMOV Linkup Auth
+///
+/// Manages the login session of a LinkedIn account: logs in, forgetting any
+/// saved login token, logs out, and reports whether the saved token is still
+/// accepted by the Linkup API.
+#[derive(Debug, Parser)]
+#[command(name = "asimov-linkup-auth", long_about)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    auth: cli::AuthOptions,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Log in and save a new login token.
+    Login,
+
+    /// Delete the saved login token.
+    Logout,
+
+    /// Report the account, token store and validity of the saved login token.
+    Status {
+        /// Output a JSON report.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[cfg(feature = "std")]
+#[tokio::main]
+async fn main() -> SysexitsError {
+    // Exit with the error's code rather than the generic failure code:
+    run().await.unwrap_or_else(|code| code)
+}
+
+#[cfg(feature = "std")]
+async fn run() -> Result<SysexitsError, SysexitsError> {
+    // Load environment variables from `.env`:
+    clientele::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    let Some(command) = options.command else {
+        return Ok(EX_OK);
+    };
+
+    let manifest = cli::read_manifest()?;
+    let account = cli::account_email(&manifest, &options.auth)?;
+    let token_store = cli::token_store(&manifest, &options.auth)?;
+
+    match command {
+        Command::Login => {
+            let client = cli::client(&cli::http_client(), &manifest, &options.auth)?;
+            client.login().await.map_err(cli::login_error)?;
+            eprintln!("Logged in as {account}.");
+        }
+
+        Command::Logout => {
+            token_store
+                .delete(&account)
+                .map_err(cli::token_store_error)?;
+            eprintln!("Logged out {account}.");
+        }
+
+        Command::Status { json } => {
+            let mut record = match token_store.load(&account) {
+                Err(e @ TokenStoreError::AccountMismatch { .. }) => {
+                    tracing::warn!("ignoring stored login token: {e}");
+                    None
+                }
+                result => result.map_err(cli::token_store_error)?,
+            };
+
+            let valid = match &mut record {
+                Some(record) => {
+                    let api_key = cli::secret_variable(&manifest, &options.auth, "linkup-api-key")?;
+                    let client = Client::builder()
+                        .login_token(record.clone())
+                        .api_key(api_key)
+                        .http_client(cli::http_client())
+                        .build();
+                    let valid = client.check_login().await.map_err(|e| {
+                        tracing::error!("failed to check login token: {e}");
+                        EX_UNAVAILABLE
+                    })?;
+                    if valid {
+                        record.last_validated_at = Some(Timestamp::now());
+                        if let Err(e) = token_store.save(record) {
+                            tracing::warn!("failed to update stored login token: {e}");
+                        }
+                    }
+                    Some(valid)
+                }
+                None => None,
+            };
+
+            let token_store = cli::token_store_kind(&manifest, &options.auth)?
+                .to_possible_value()
+                .unwrap();
+            let issued_at = record.as_ref().and_then(|record| record.issued_at);
+            let token_age = record
+                .as_ref()
+                .and_then(|record| record.age())
+                .map(|age| SignedDuration::from_secs(age.as_secs()));
+            let last_validated_at = record.as_ref().and_then(|record| record.last_validated_at);
+
+            let mut stdout = std::io::stdout().lock();
+            if json {
+                let report = serde_json::json!({
+                    "account": account,
+                    "profile": options.auth.account.as_deref().unwrap_or("default"),
+                    "token_store": token_store.get_name(),
+                    "logged_in": valid.is_some(),
+                    "valid": valid,
+                    "issued_at": issued_at,
+                    "token_age": token_age.map(|age| std::format!("{age:#}")),
+                    "last_validated_at": last_validated_at,
+                });
+                serde_json::to_writer(&mut stdout, &report).unwrap();
+                writeln!(&mut stdout).unwrap();
+            } else {
+                let session = match valid {
+                    Some(true) => "logged in",
+                    Some(false) => "logged in, token expired",
+                    None => "logged out",
+                };
+                let unknown = || "unknown".to_string();
+                writeln!(&mut stdout, "account: {account}").unwrap();
+                writeln!(&mut stdout, "token store: {}", token_store.get_name()).unwrap();
+                writeln!(&mut stdout, "session: {session}").unwrap();
+                if record.is_some() {
+                    let issued_at = issued_at.map_or_else(unknown, |at| at.to_string());
+                    let token_age = token_age.map_or_else(unknown, |age| std::format!("{age:#}"));
+                    let last_validated_at =
+                        last_validated_at.map_or_else(unknown, |at| at.to_string());
+                    writeln!(&mut stdout, "issued at: {issued_at}").unwrap();
+                    writeln!(&mut stdout, "token age: {token_age}").unwrap();
+                    writeln!(&mut stdout, "last validated at: {last_validated_at}").unwrap();
+                }
+            }
+        }
+    }
+
+    Ok(EX_OK)
+}
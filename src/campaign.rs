@@ -0,0 +1,271 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Multi-step outreach campaigns with durable per-recipient state.
+
+use crate::{Template, TemplateError};
+use jiff::Timestamp;
+use redb::{ReadableTable, TableDefinition};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::string::String;
+use std::vec::Vec;
+
+const CAMPAIGNS: TableDefinition<&str, &str> = TableDefinition::new("campaigns");
+const RECIPIENTS: TableDefinition<(&str, &str), &str> = TableDefinition::new("recipients");
+
+/// An outreach sequence: invite, wait for acceptance, message, and follow up
+/// after a number of days without a reply.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Campaign {
+    pub name: String,
+    /// The template of the invitation note, if any.
+    pub invite: Option<String>,
+    /// The template of the message sent once the invitation is accepted.
+    pub message: String,
+    /// The template of the follow-up message, if any.
+    pub follow_up: Option<String>,
+    /// The number of days without a reply after which to follow up.
+    pub follow_up_after_days: u32,
+}
+
+impl Campaign {
+    /// Parses the campaign's templates, verifying that they are well-formed.
+    pub fn templates(&self) -> Result<CampaignTemplates, TemplateError> {
+        Ok(CampaignTemplates {
+            invite: self.invite.as_deref().map(Template::parse).transpose()?,
+            message: Template::parse(&self.message)?,
+            follow_up: self.follow_up.as_deref().map(Template::parse).transpose()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CampaignTemplates {
+    pub invite: Option<Template>,
+    pub message: Template,
+    pub follow_up: Option<Template>,
+}
+
+/// A recipient's progress through a campaign.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "state", rename_all = "kebab-case")]
+pub enum RecipientState {
+    Pending,
+    Invited {
+        at: Timestamp,
+    },
+    Connected {
+        at: Timestamp,
+    },
+    Messaged {
+        at: Timestamp,
+    },
+    FollowedUp {
+        at: Timestamp,
+    },
+    /// An invitation or message is being sent, as recorded before sending
+    /// it. A recipient left in this state may or may not have received it.
+    Sending {
+        at: Timestamp,
+        action: String,
+    },
+    Replied {
+        at: Timestamp,
+    },
+    Failed {
+        at: Timestamp,
+        error: String,
+    },
+}
+
+impl RecipientState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Invited { .. } => "invited",
+            Self::Connected { .. } => "connected",
+            Self::Messaged { .. } => "messaged",
+            Self::FollowedUp { .. } => "followed-up",
+            Self::Sending { .. } => "sending",
+            Self::Replied { .. } => "replied",
+            Self::Failed { .. } => "failed",
+        }
+    }
+
+    /// Returns when the recipient entered this state.
+    pub fn since(&self) -> Option<Timestamp> {
+        match self {
+            Self::Pending => None,
+            Self::Invited { at }
+            | Self::Connected { at }
+            | Self::Messaged { at }
+            | Self::FollowedUp { at }
+            | Self::Sending { at, .. }
+            | Self::Replied { at }
+            | Self::Failed { at, .. } => Some(*at),
+        }
+    }
+
+    /// Returns whether the campaign is over for the recipient.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Replied { .. } | Self::Failed { .. })
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Recipient {
+    /// The recipient's profile URL.
+    pub url: String,
+    /// The template variables of the recipient.
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub state: RecipientState,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CampaignError {
+    #[error("campaign database error: {0}")]
+    Database(#[source] std::boxed::Box<redb::Error>),
+    #[error("invalid campaign data: {0}")]
+    InvalidData(#[from] serde_json::Error),
+    #[error("campaign `{0}` already exists")]
+    AlreadyExists(String),
+    #[error("campaign `{0}` not found")]
+    NotFound(String),
+}
+
+macro_rules! impl_from_redb {
+    ($($error:ty),*) => {
+        $(impl From<$error> for CampaignError {
+            fn from(value: $error) -> Self {
+                Self::Database(std::boxed::Box::new(value.into()))
+            }
+        })*
+    };
+}
+
+impl_from_redb!(
+    redb::Error,
+    redb::DatabaseError,
+    redb::TransactionError,
+    redb::TableError,
+    redb::StorageError,
+    redb::CommitError
+);
+
+/// A local database of campaigns and their recipients.
+#[derive(Debug)]
+pub struct CampaignStore {
+    db: redb::Database,
+}
+
+impl CampaignStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CampaignError> {
+        let db = redb::Database::create(path)?;
+
+        let txn = db.begin_write()?;
+        txn.open_table(CAMPAIGNS)?;
+        txn.open_table(RECIPIENTS)?;
+        txn.commit()?;
+
+        Ok(Self { db })
+    }
+
+    pub fn create_campaign(&self, campaign: &Campaign) -> Result<(), CampaignError> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(CAMPAIGNS)?;
+            if table.get(campaign.name.as_str())?.is_some() {
+                return Err(CampaignError::AlreadyExists(campaign.name.clone()));
+            }
+            let value = serde_json::to_string(campaign)?;
+            table.insert(campaign.name.as_str(), value.as_str())?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    pub fn campaign(&self, name: &str) -> Result<Campaign, CampaignError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(CAMPAIGNS)?;
+        match table.get(name)? {
+            Some(value) => Ok(serde_json::from_str(value.value())?),
+            None => Err(CampaignError::NotFound(name.into())),
+        }
+    }
+
+    pub fn campaigns(&self) -> Result<Vec<Campaign>, CampaignError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(CAMPAIGNS)?;
+        let mut campaigns = Vec::new();
+        for entry in table.iter()? {
+            let (_, value) = entry?;
+            campaigns.push(serde_json::from_str(value.value())?);
+        }
+        Ok(campaigns)
+    }
+
+    /// Adds pending recipients to a campaign, skipping those already in it,
+    /// and returns the number of recipients added.
+    pub fn add_recipients(
+        &self,
+        campaign: &str,
+        recipients: impl IntoIterator<Item = (String, BTreeMap<String, String>)>,
+    ) -> Result<usize, CampaignError> {
+        self.campaign(campaign)?;
+
+        let mut added = 0;
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(RECIPIENTS)?;
+            for (url, variables) in recipients {
+                if table.get((campaign, url.as_str()))?.is_some() {
+                    continue;
+                }
+                let recipient = Recipient {
+                    url,
+                    variables,
+                    state: RecipientState::Pending,
+                };
+                let value = serde_json::to_string(&recipient)?;
+                table.insert((campaign, recipient.url.as_str()), value.as_str())?;
+                added += 1;
+            }
+        }
+        txn.commit()?;
+        Ok(added)
+    }
+
+    pub fn recipients(&self, campaign: &str) -> Result<Vec<Recipient>, CampaignError> {
+        self.campaign(campaign)?;
+
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(RECIPIENTS)?;
+        let mut recipients = Vec::new();
+        for entry in table.range((campaign, "")..)? {
+            let (key, value) = entry?;
+            if key.value().0 != campaign {
+                break;
+            }
+            recipients.push(serde_json::from_str(value.value())?);
+        }
+        Ok(recipients)
+    }
+
+    /// Durably saves a recipient's state.
+    pub fn update_recipient(
+        &self,
+        campaign: &str,
+        recipient: &Recipient,
+    ) -> Result<(), CampaignError> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(RECIPIENTS)?;
+            let value = serde_json::to_string(recipient)?;
+            table.insert((campaign, recipient.url.as_str()), value.as_str())?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+}
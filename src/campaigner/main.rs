@@ -0,0 +1,689 @@
+// This is free and unencumbered software released into the public domain.
+
+use asimov_linkup_module::{
+    Client, RateLimiter, Template,
+    campaign::{Campaign, CampaignError, CampaignStore, Recipient, RecipientState},
+    cli,
+    error::{ApiErrorKind, RequestError, WriteError},
+    merge_profile_variables, parse_timestamp,
+};
+use asimov_module::SysexitsError::{self, *};
+use clientele::{
+    StandardOptions,
+    crates::clap::{self, Parser, Subcommand},
+};
+use jiff::{SignedDuration, Timestamp};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    path::PathBuf,
+};
+
+#[cfg(not(feature = "std"))]
+fn main() {
+    unimplemented!("asimov-linkup-campaigner requires the 'std' feature")
+}
+
+/// ASIMOV Linkup Campaigner
+///
+/// Drives multi-step outreach campaigns: each recipient is invited, messaged
+/// once the invitation is accepted, and followed up with after a number of
+/// days without a reply. Campaign state is kept in
+/// `~/.asimov/state/linkup/campaigns.redb`, so `run` can be repeated (e.g.
+/// daily) and resumes where the previous run left off.
+///
+/// Every performed action is appended to `~/.asimov/state/linkup/audit.jsonl`.
+#[derive(Debug, Parser)]
+#[command(name = "asimov-linkup-campaigner", long_about, after_long_help = cli::CONFIRMED_EXIT_STATUS)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    auth: cli::AuthOptions,
+
+    #[clap(flatten)]
+    credits: cli::CreditOptions,
+
+    /// Run without asking for confirmation, as required without a terminal.
+    #[arg(short = 'y', long)]
+    yes: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Create a campaign from message template files.
+    Create {
+        /// The template file of the invitation note.
+        #[arg(value_name = "FILE", long)]
+        invite: Option<PathBuf>,
+
+        /// The template file of the message sent once connected.
+        #[arg(value_name = "FILE", long)]
+        message: PathBuf,
+
+        /// The template file of the follow-up message.
+        #[arg(value_name = "FILE", long)]
+        follow_up: Option<PathBuf>,
+
+        /// The number of days without a reply after which to follow up.
+        #[arg(value_name = "DAYS", long, default_value_t = 3)]
+        follow_up_after: u32,
+
+        /// The campaign name.
+        name: String,
+    },
+
+    /// Add recipients from a CSV file to a campaign.
+    Add {
+        /// The CSV column containing the profile URLs.
+        #[arg(value_name = "NAME", long, default_value = "url")]
+        url_column: String,
+
+        /// The campaign name.
+        name: String,
+
+        /// The CSV file with one recipient per row.
+        recipients: PathBuf,
+    },
+
+    /// Advance every recipient of a campaign as far as currently possible.
+    Run {
+        /// The maximum number of API requests per minute.
+        #[arg(value_name = "COUNT", long, default_value_t = 20)]
+        rate: u32,
+
+        /// The campaign name.
+        name: String,
+    },
+
+    /// Report the state of campaigns and their recipients.
+    Status {
+        /// Output a JSON report.
+        #[arg(long)]
+        json: bool,
+
+        /// The campaign name; all campaigns if omitted.
+        name: Option<String>,
+    },
+}
+
+#[cfg(feature = "std")]
+#[tokio::main]
+async fn main() -> SysexitsError {
+    // Exit with the error's code rather than the generic failure code:
+    run().await.unwrap_or_else(|code| code)
+}
+
+#[cfg(feature = "std")]
+async fn run() -> Result<SysexitsError, SysexitsError> {
+    // Load environment variables from `.env`:
+    clientele::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    let Some(command) = options.command else {
+        return Ok(EX_OK);
+    };
+
+    std::fs::create_dir_all(cli::state_dir()).map_err(|e| {
+        tracing::error!("failed to create state directory: {e}");
+        EX_CANTCREAT
+    })?;
+    let store = CampaignStore::open(cli::state_dir().join("campaigns.redb")).map_err(exit_code)?;
+
+    match command {
+        Command::Create {
+            invite,
+            message,
+            follow_up,
+            follow_up_after,
+            name,
+        } => {
+            let campaign = Campaign {
+                name,
+                invite: invite
+                    .as_deref()
+                    .map(|path| cli::read_text(Some(path)))
+                    .transpose()?,
+                message: cli::read_text(Some(&message))?,
+                follow_up: follow_up
+                    .as_deref()
+                    .map(|path| cli::read_text(Some(path)))
+                    .transpose()?,
+                follow_up_after_days: follow_up_after,
+            };
+            campaign.templates().map_err(|e| {
+                tracing::error!("invalid template: {e}");
+                EX_DATAERR
+            })?;
+            store.create_campaign(&campaign).map_err(exit_code)?;
+        }
+
+        Command::Add {
+            url_column,
+            name,
+            recipients,
+        } => {
+            let recipients = cli::read_recipients(&recipients, &url_column)?;
+            let added = store.add_recipients(&name, recipients).map_err(exit_code)?;
+            tracing::info!("added {added} recipients to campaign `{name}`");
+        }
+
+        Command::Run { rate, name } => {
+            let campaign = store.campaign(&name).map_err(exit_code)?;
+
+            if !options.yes {
+                let prompt = format!("Run campaign `{name}`?");
+                cli::confirm(&prompt)?;
+            }
+
+            let manifest = cli::read_manifest()?;
+            let http_client = cli::http_client();
+            let mut client = cli::connect(&http_client, &manifest, &options.auth).await?;
+            client.credit_budget = options.credits.budget();
+            client.rate_limiter = Some(RateLimiter::per_minute(rate));
+
+            let mut runner = Runner {
+                client,
+                store: &store,
+                campaign,
+            };
+            runner.run().await?;
+        }
+
+        Command::Status { json, name } => {
+            let campaigns = match name {
+                Some(name) => vec![store.campaign(&name).map_err(exit_code)?],
+                None => store.campaigns().map_err(exit_code)?,
+            };
+
+            let mut stdout = std::io::stdout().lock();
+            for campaign in campaigns {
+                let recipients = store.recipients(&campaign.name).map_err(exit_code)?;
+
+                let mut counts = BTreeMap::<&str, usize>::new();
+                for recipient in &recipients {
+                    *counts.entry(recipient.state.name()).or_default() += 1;
+                }
+
+                if json {
+                    let report = serde_json::json!({
+                        "campaign": campaign.name,
+                        "counts": counts,
+                        "recipients": recipients,
+                    });
+                    serde_json::to_writer(&mut stdout, &report).unwrap();
+                    writeln!(&mut stdout).unwrap();
+                    continue;
+                }
+
+                writeln!(&mut stdout, "{}:", campaign.name).unwrap();
+                for (state, count) in counts {
+                    writeln!(&mut stdout, "  {state}: {count}").unwrap();
+                }
+                for recipient in recipients {
+                    let since = recipient
+                        .state
+                        .since()
+                        .map(|at| at.to_string())
+                        .unwrap_or_default();
+                    writeln!(
+                        &mut stdout,
+                        "  {} {} {since}",
+                        recipient.url,
+                        recipient.state.name()
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    Ok(EX_OK)
+}
+
+fn exit_code(error: CampaignError) -> SysexitsError {
+    tracing::error!("{error}");
+    match error {
+        CampaignError::Database(_) => EX_IOERR,
+        CampaignError::InvalidData(_) => EX_DATAERR,
+        CampaignError::AlreadyExists(_) => EX_CANTCREAT,
+        CampaignError::NotFound(_) => EX_NOINPUT,
+    }
+}
+
+struct Runner<'a> {
+    client: Client,
+    store: &'a CampaignStore,
+    campaign: Campaign,
+}
+
+impl Runner<'_> {
+    async fn run(&mut self) -> Result<(), SysexitsError> {
+        let mut recipients = self
+            .store
+            .recipients(&self.campaign.name)
+            .map_err(exit_code)?;
+        recipients.retain(|recipient| !recipient.state.is_final());
+
+        // Acceptance is detected by looking for recipients among connections:
+        let connections = if recipients.iter().any(|recipient| {
+            matches!(
+                recipient.state,
+                RecipientState::Pending | RecipientState::Invited { .. }
+            )
+        }) {
+            let connections = self
+                .client
+                .fetch_connections()
+                .await
+                .map_err(cli::fetch_error)?;
+            connections.iter().flat_map(profile_paths).collect()
+        } else {
+            BTreeSet::new()
+        };
+
+        // Replies are detected by looking for recipients' last messages in the inbox:
+        let replies = if recipients.iter().any(|recipient| {
+            matches!(
+                recipient.state,
+                RecipientState::Messaged { .. } | RecipientState::FollowedUp { .. }
+            )
+        }) {
+            let inbox = self.client.fetch_inbox().await.map_err(cli::fetch_error)?;
+            last_messages(&inbox)
+        } else {
+            BTreeMap::new()
+        };
+
+        for mut recipient in recipients {
+            let path = cli::audit_target(&recipient.url);
+            let connected = connections.contains(&path);
+            let replied_at = replies.get(&path);
+
+            while let Some(state) = self.step(&mut recipient, connected, replied_at).await? {
+                tracing::info!(
+                    url = recipient.url,
+                    "{} -> {}",
+                    recipient.state.name(),
+                    state.name()
+                );
+                recipient.state = state;
+                self.store
+                    .update_recipient(&self.campaign.name, &recipient)
+                    .map_err(exit_code)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the recipient's next state, if it can advance now.
+    async fn step(
+        &mut self,
+        recipient: &mut Recipient,
+        connected: bool,
+        replied_at: Option<&Option<Timestamp>>,
+    ) -> Result<Option<RecipientState>, SysexitsError> {
+        let now = Timestamp::now();
+        let templates = self.campaign.templates().map_err(|e| {
+            tracing::error!("invalid template: {e}");
+            EX_DATAERR
+        })?;
+
+        Ok(match recipient.state {
+            RecipientState::Pending if connected => Some(RecipientState::Connected { at: now }),
+            RecipientState::Pending => {
+                let note = match &templates.invite {
+                    Some(template) => match self.render(template, recipient).await? {
+                        Ok(note) => Some(note),
+                        Err(state) => return Ok(Some(state)),
+                    },
+                    None => None,
+                };
+                let sent = RecipientState::Invited { at: now };
+                let result = self.write(recipient, "send-invitation", note.as_deref(), sent);
+                Some(result.await?)
+            }
+            RecipientState::Invited { .. } if connected => {
+                Some(RecipientState::Connected { at: now })
+            }
+            RecipientState::Connected { .. } => {
+                let text = match self.render(&templates.message, recipient).await? {
+                    Ok(text) => text,
+                    Err(state) => return Ok(Some(state)),
+                };
+                let sent = RecipientState::Messaged { at: now };
+                let result = self.write(recipient, "send-message", Some(&text), sent);
+                Some(result.await?)
+            }
+            RecipientState::Messaged { at } | RecipientState::FollowedUp { at }
+                if replied_since(replied_at, at) =>
+            {
+                Some(RecipientState::Replied { at: now })
+            }
+            RecipientState::Messaged { at } => {
+                let follow_up_after =
+                    SignedDuration::from_hours(24 * i64::from(self.campaign.follow_up_after_days));
+                match &templates.follow_up {
+                    Some(template) if now.duration_since(at) >= follow_up_after => {
+                        let text = match self.render(template, recipient).await? {
+                            Ok(text) => text,
+                            Err(state) => return Ok(Some(state)),
+                        };
+                        let sent = RecipientState::FollowedUp { at: now };
+                        let result = self.write(recipient, "send-message", Some(&text), sent);
+                        Some(result.await?)
+                    }
+                    _ => None,
+                }
+            }
+            // A send interrupted before its outcome was known must not be
+            // repeated blindly:
+            RecipientState::Sending { ref action, .. } => Some(RecipientState::Failed {
+                at: now,
+                error: format!(
+                    "interrupted while sending ({action}), check whether it was received \
+                    before retrying"
+                ),
+            }),
+            _ => None,
+        })
+    }
+
+    /// Renders a template for the recipient, fetching their profile if the
+    /// template needs variables the recipient doesn't have yet.
+    async fn render(
+        &mut self,
+        template: &Template,
+        recipient: &mut Recipient,
+    ) -> Result<Result<String, RecipientState>, SysexitsError> {
+        let missing = template.variables().any(|name| {
+            recipient
+                .variables
+                .get(name)
+                .is_none_or(|value| value.trim().is_empty())
+        });
+
+        if missing {
+            let url = recipient.url.clone();
+            let profile = self.client.fetch(&url).await.map_err(cli::fetch_error)?;
+            merge_profile_variables(&mut recipient.variables, &profile);
+        }
+
+        Ok(template
+            .render(&recipient.variables)
+            .map_err(|e| RecipientState::Failed {
+                at: Timestamp::now(),
+                error: e.to_string(),
+            }))
+    }
+
+    /// Sends an invitation or message and records it in the audit log,
+    /// returning the recipient's `sent` state, or failed state if it was
+    /// rejected.
+    ///
+    /// The recipient is saved as [`Sending`](RecipientState::Sending) until
+    /// the outcome is known, so that a run aborted midway doesn't send again.
+    async fn write(
+        &mut self,
+        recipient: &Recipient,
+        action: &str,
+        text: Option<&str>,
+        sent: RecipientState,
+    ) -> Result<RecipientState, SysexitsError> {
+        let sending = RecipientState::Sending {
+            at: Timestamp::now(),
+            action: action.into(),
+        };
+        self.save(recipient, sending)?;
+
+        let result = self.perform(action, &recipient.url, text).await;
+
+        let state = match &result {
+            Ok(_) => sent,
+            Err(WriteError::Request(e))
+                if e.is_retryable() || e.is_auth() || e.is_budget_exceeded() =>
+            {
+                // Network, server and login failures, and running out of
+                // budgeted credits, abort the run, to be resumed later. The
+                // send is retried then only if it certainly didn't happen:
+                if !maybe_sent(e) {
+                    self.save(recipient, recipient.state.clone())?;
+                }
+                cli::audit(action, &recipient.url, &result)?;
+                return Err(cli::write_error(result.unwrap_err()));
+            }
+            Err(e) => RecipientState::Failed {
+                at: Timestamp::now(),
+                error: e.to_string(),
+            },
+        };
+
+        // Save the outcome before auditing it, lest it be lost:
+        self.save(recipient, state.clone())?;
+        cli::audit(action, &recipient.url, &result)?;
+        Ok(state)
+    }
+
+    /// Saves the recipient in the given state.
+    fn save(&self, recipient: &Recipient, state: RecipientState) -> Result<(), SysexitsError> {
+        let recipient = Recipient {
+            state,
+            ..recipient.clone()
+        };
+        self.store
+            .update_recipient(&self.campaign.name, &recipient)
+            .map_err(exit_code)
+    }
+
+    async fn perform(
+        &self,
+        action: &str,
+        url: &str,
+        text: Option<&str>,
+    ) -> Result<serde_json::Value, WriteError> {
+        match action {
+            "send-invitation" => self.client.send_invitation(url, text).await,
+            _ => {
+                self.client
+                    .send_message(url, text.unwrap_or_default())
+                    .await
+            }
+        }
+    }
+}
+
+/// Returns whether a failed request may have been carried out nonetheless,
+/// as when the response timed out or the server failed.
+fn maybe_sent(error: &RequestError) -> bool {
+    match error {
+        RequestError::Http(e) => !e.is_connect(),
+        RequestError::Api { kind, .. } => *kind == ApiErrorKind::Server,
+        _ => false,
+    }
+}
+
+/// Collects the normalized LinkedIn profile paths (`/in/:account`) found
+/// anywhere in a JSON value.
+fn profile_paths(value: &serde_json::Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            serde_json::Value::String(s) if s.contains("linkedin.com/in/") => {
+                paths.push(cli::audit_target(s));
+            }
+            serde_json::Value::Array(values) => stack.extend(values),
+            serde_json::Value::Object(map) => stack.extend(map.values()),
+            _ => {}
+        }
+    }
+    paths
+}
+
+/// Returns the normalized profile path of the sender of a message, unless it
+/// was sent by the logged-in account or has no sender.
+///
+/// Only the sender is considered, as messages may also list the profiles of
+/// their recipients or the conversation's participants.
+fn sender_path(message: &serde_json::Value) -> Option<String> {
+    let is_own = |value: &serde_json::Value| {
+        ["is_me", "is_self", "is_sender", "from_me", "sent_by_me"]
+            .iter()
+            .any(|flag| value[flag].as_bool() == Some(true))
+    };
+    if is_own(message) {
+        return None;
+    }
+
+    let sender = ["sender", "from", "author"]
+        .iter()
+        .find_map(|key| message.get(key).filter(|sender| !sender.is_null()))?;
+    if is_own(sender) {
+        return None;
+    }
+
+    let url = match sender {
+        serde_json::Value::String(url) => url.clone(),
+        sender => match ["profile_url", "linkedin_url", "url"]
+            .iter()
+            .find_map(|key| sender[key].as_str())
+        {
+            Some(url) => url.into(),
+            None => format!(
+                "https://www.linkedin.com/in/{}",
+                sender["public_identifier"].as_str()?
+            ),
+        },
+    };
+    url.contains("linkedin.com/in/")
+        .then(|| cli::audit_target(&url))
+}
+
+/// Maps the profile paths of the senders of each conversation's last message
+/// to when it was sent, if known. Messages sent by the logged-in account are
+/// left out.
+fn last_messages(inbox: &[serde_json::Value]) -> BTreeMap<String, Option<Timestamp>> {
+    let mut senders = BTreeMap::new();
+    for conversation in inbox {
+        let Some(last_message) = conversation.get("last_message") else {
+            continue;
+        };
+        let sent_at = ["timestamp", "sent_at", "created_at", "last_activity_at"]
+            .iter()
+            .find_map(|key| {
+                let value = last_message.get(key).or_else(|| conversation.get(key))?;
+                parse_timestamp(value)
+            });
+        if let Some(path) = sender_path(last_message) {
+            senders.insert(path, sent_at);
+        }
+    }
+    senders
+}
+
+/// Returns whether the recipient's last message, sent at `replied_at` if
+/// known, is a reply after `at`. Messages of unknown time don't count, as
+/// they may precede `at`, e.g. the recipient's own message just answered.
+fn replied_since(replied_at: Option<&Option<Timestamp>>, at: Timestamp) -> bool {
+    matches!(replied_at, Some(Some(replied_at)) if *replied_at > at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn detects_replies_from_the_sender() {
+        let inbox = [json!({
+            "conversation_id": "1",
+            "participants": [{ "profile_url": "https://www.linkedin.com/in/ada" }],
+            "last_message": {
+                "sender": { "profile_url": "https://linkedin.com/in/Ada/" },
+                "recipients": ["https://www.linkedin.com/in/me"],
+                "timestamp": "2025-01-02T03:04:05Z",
+            },
+        })];
+        let replies = last_messages(&inbox);
+        assert_eq!(replies.len(), 1);
+        assert_eq!(
+            replies["/in/ada"],
+            Some("2025-01-02T03:04:05Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn ignores_messages_sent_by_the_logged_in_account() {
+        let inbox = [
+            // Our own follow-up, listing the recipient's profile:
+            json!({
+                "participants": [{ "profile_url": "https://www.linkedin.com/in/ada" }],
+                "last_message": {
+                    "sender": { "profile_url": "https://www.linkedin.com/in/me", "is_me": true },
+                    "recipient": { "profile_url": "https://www.linkedin.com/in/ada" },
+                },
+            }),
+            json!({
+                "last_message": {
+                    "from_me": true,
+                    "from": "https://www.linkedin.com/in/me",
+                    "text": "See https://www.linkedin.com/in/grace",
+                },
+            }),
+            // No sender at all:
+            json!({
+                "last_message": { "text": "https://www.linkedin.com/in/grace" },
+            }),
+        ];
+        assert_eq!(last_messages(&inbox), BTreeMap::new());
+    }
+
+    #[test]
+    fn finds_the_sender_in_alternative_fields() {
+        let message = json!({ "author": { "public_identifier": "grace" } });
+        assert_eq!(sender_path(&message).as_deref(), Some("/in/grace"));
+        let message = json!({ "from": "https://www.linkedin.com/in/alan/" });
+        assert_eq!(sender_path(&message).as_deref(), Some("/in/alan"));
+        let message = json!({ "sender": "https://example.com/alan" });
+        assert_eq!(sender_path(&message), None);
+    }
+
+    #[test]
+    fn only_replies_known_to_be_later_count() {
+        let at: Timestamp = "2025-01-02T03:04:05Z".parse().unwrap();
+        let before: Timestamp = "2025-01-01T00:00:00Z".parse().unwrap();
+        let after: Timestamp = "2025-01-03T00:00:00Z".parse().unwrap();
+        assert!(replied_since(Some(&Some(after)), at));
+        assert!(!replied_since(Some(&Some(before)), at));
+        assert!(!replied_since(Some(&Some(at)), at));
+        // A last message of unknown time, maybe the one just answered:
+        assert!(!replied_since(Some(&None), at));
+        assert!(!replied_since(None, at));
+    }
+}
@@ -0,0 +1,946 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Shared support for the module's command-line programs.
+
+#[cfg(feature = "keyring")]
+use crate::KeyringTokenStore;
+use crate::{
+    Client, Credentials, CreditBudget, FileTokenStore, MemoryTokenStore, TokenStore,
+    TokenStoreError,
+    error::{ApiErrorKind, FetchError, LoginError, RequestError, VerifyError},
+};
+use asimov_module::{
+    ModuleManifest,
+    SysexitsError::{self, *},
+    secrecy::{ExposeSecret, SecretString},
+};
+use clientele::crates::clap;
+use std::{
+    boxed::Box,
+    collections::BTreeMap,
+    io::{BufRead, Read, Write},
+    path::{Path, PathBuf},
+    string::String,
+    sync::Arc,
+    time::Duration,
+    vec::Vec,
+};
+
+pub fn read_manifest() -> Result<ModuleManifest, SysexitsError> {
+    ModuleManifest::read_manifest("linkup").map_err(|e| {
+        tracing::error!("failed to read module manifest: {e}");
+        EX_CONFIG
+    })
+}
+
+/// Returns the directory for the module's local state, `~/.asimov/state/linkup`.
+pub fn state_dir() -> PathBuf {
+    asimov_env::paths::asimov_root()
+        .join("state")
+        .join("linkup")
+}
+
+/// Reads text from a file, or from stdin when no file is given.
+pub fn read_text(file: Option<&Path>) -> Result<String, SysexitsError> {
+    let text = match file {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| {
+            tracing::error!("failed to read text from `{}`: {e}", path.display());
+            EX_NOINPUT
+        })?,
+        None => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).map_err(|e| {
+                tracing::error!("failed to read text from stdin: {e}");
+                EX_IOERR
+            })?;
+            text
+        }
+    };
+
+    let text = text.trim();
+    if text.is_empty() {
+        tracing::error!("text is empty");
+        return Err(EX_DATAERR);
+    }
+
+    Ok(text.into())
+}
+
+/// A recipient's profile URL and template variables.
+pub type Recipient = (String, BTreeMap<String, String>);
+
+/// Reads recipients from a CSV file, returning each one's profile URL from
+/// `url_column` along with the remaining columns.
+pub fn read_recipients(path: &Path, url_column: &str) -> Result<Vec<Recipient>, SysexitsError> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| {
+        tracing::error!("failed to read recipients `{}`: {e}", path.display());
+        EX_NOINPUT
+    })?;
+
+    let headers = reader
+        .headers()
+        .map_err(|e| {
+            tracing::error!("failed to read recipients `{}`: {e}", path.display());
+            EX_DATAERR
+        })?
+        .clone();
+
+    if !headers.iter().any(|header| header == url_column) {
+        tracing::error!(
+            "recipients `{}` have no `{url_column}` column",
+            path.display()
+        );
+        return Err(EX_DATAERR);
+    }
+
+    let mut recipients = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| {
+            tracing::error!("failed to read recipients `{}`: {e}", path.display());
+            EX_DATAERR
+        })?;
+
+        let mut variables: BTreeMap<String, String> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect();
+
+        match variables.remove(url_column) {
+            Some(url) if !url.trim().is_empty() => recipients.push((url.trim().into(), variables)),
+            _ => continue,
+        }
+    }
+
+    Ok(recipients)
+}
+
+/// The format of a file listing URLs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum InputFormat {
+    /// One URL per line. Lines that are JSON objects are read as JSONL.
+    Lines,
+    /// CSV with a header row, the URLs in the URL column.
+    Csv,
+    /// One JSON object per line, the URLs in the URL field.
+    Jsonl,
+}
+
+impl InputFormat {
+    /// Guesses the format of a file from its extension.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Self::Csv,
+            Some("jsonl" | "ndjson" | "json") => Self::Jsonl,
+            _ => Self::Lines,
+        }
+    }
+}
+
+/// Reads URLs from a file, or from stdin if the path is `-`, in the given
+/// format or else the one suggested by the file's extension.
+///
+/// CSV URLs are read from `url_column`, and JSON URLs from `url_field`,
+/// which may also be a JSON pointer such as `/profile/url`.
+pub fn read_urls(
+    path: &Path,
+    format: Option<InputFormat>,
+    url_column: &str,
+    url_field: &str,
+) -> Result<Vec<String>, SysexitsError> {
+    let (reader, name): (Box<dyn BufRead>, _) = if path == Path::new("-") {
+        (Box::new(std::io::stdin().lock()), "stdin".into())
+    } else {
+        let file = std::fs::File::open(path).map_err(|e| {
+            tracing::error!("failed to read URLs from `{}`: {e}", path.display());
+            EX_NOINPUT
+        })?;
+        let name = std::format!("`{}`", path.display());
+        (Box::new(std::io::BufReader::new(file)), name)
+    };
+    let format = format.unwrap_or_else(|| InputFormat::from_path(path));
+
+    let mut urls = Vec::new();
+    match format {
+        InputFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(reader);
+            let failed = |e: csv::Error| {
+                tracing::error!("failed to read URLs from {name}: {e}");
+                EX_DATAERR
+            };
+            let Some(column) = reader
+                .headers()
+                .map_err(failed)?
+                .iter()
+                .position(|header| header == url_column)
+            else {
+                tracing::error!("{name} has no `{url_column}` column");
+                return Err(EX_DATAERR);
+            };
+            for record in reader.records() {
+                match record.map_err(failed)?.get(column).map(str::trim) {
+                    Some(url) if !url.is_empty() => urls.push(url.into()),
+                    _ => continue,
+                }
+            }
+        }
+        InputFormat::Lines | InputFormat::Jsonl => {
+            for (number, line) in reader.lines().enumerate() {
+                let line = line.map_err(|e| {
+                    tracing::error!("failed to read URLs from {name}: {e}");
+                    EX_IOERR
+                })?;
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                if format == InputFormat::Lines && !line.starts_with('{') {
+                    urls.push(line.into());
+                    continue;
+                }
+
+                let value: serde_json::Value = serde_json::from_str(line).map_err(|e| {
+                    tracing::error!("invalid JSON on line {} of {name}: {e}", number + 1);
+                    EX_DATAERR
+                })?;
+                let url = if url_field.starts_with('/') {
+                    value.pointer(url_field)
+                } else {
+                    value.get(url_field)
+                };
+                match url.and_then(|url| url.as_str()).map(str::trim) {
+                    Some(url) if !url.is_empty() => urls.push(url.into()),
+                    _ => tracing::warn!("no `{url_field}` on line {} of {name}", number + 1),
+                }
+            }
+        }
+    }
+
+    Ok(urls)
+}
+
+/// Asks the user on stderr to confirm an action, reading the answer from the
+/// terminal, as stdin may be the input of the action.
+///
+/// Fails with [`EX_CONFIRMATION_REQUIRED`] when there is no terminal to ask
+/// on, and with [`EX_DECLINED`] unless the user confirms.
+pub fn confirm(prompt: &str) -> Result<(), SysexitsError> {
+    let terminal = open_terminal().map_err(|e| {
+        tracing::error!("confirmation required, but there is no terminal to ask on ({e}); pass `--yes` to proceed");
+        EX_CONFIRMATION_REQUIRED
+    })?;
+
+    let mut stderr = std::io::stderr().lock();
+    std::write!(&mut stderr, "{prompt} [y/N] ").unwrap();
+    stderr.flush().unwrap();
+
+    let mut answer = String::new();
+    std::io::BufReader::new(terminal)
+        .read_line(&mut answer)
+        .map_err(|e| {
+            tracing::error!("error while reading input: {e}");
+            EX_IOERR
+        })?;
+
+    if !matches!(answer.trim(), "y" | "Y" | "yes" | "YES") {
+        tracing::error!("not confirmed, nothing was done");
+        return Err(EX_DECLINED);
+    }
+    Ok(())
+}
+
+/// Opens the controlling terminal for reading.
+fn open_terminal() -> std::io::Result<std::fs::File> {
+    #[cfg(windows)]
+    let path = "CONIN$";
+    #[cfg(not(windows))]
+    let path = "/dev/tty";
+    std::fs::File::open(path)
+}
+
+/// Appends a record of a performed write action to the audit log at
+/// `~/.asimov/state/linkup/audit.jsonl`.
+#[cfg(feature = "write")]
+pub fn audit(
+    action: &str,
+    target: &str,
+    result: &Result<serde_json::Value, crate::error::WriteError>,
+) -> Result<(), SysexitsError> {
+    let mut record = serde_json::json!({
+        "time": jiff::Timestamp::now(),
+        "action": action,
+        "target": target,
+    });
+    match result {
+        Ok(response) => record["response"] = response.clone(),
+        Err(e) => record["error"] = std::format!("{e}").into(),
+    }
+
+    let path = state_dir().join("audit.jsonl");
+    let write = || -> std::io::Result<()> {
+        std::fs::create_dir_all(state_dir())?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        writeln!(file, "{record}")
+    };
+
+    write().map_err(|e| {
+        tracing::error!("failed to write audit log `{}`: {e}", path.display());
+        EX_IOERR
+    })
+}
+
+/// Returns the profiles and conversations that `action`, e.g. `send-message`,
+/// was successfully performed on according to the audit log, as normalized
+/// by [`audit_target`].
+#[cfg(feature = "write")]
+pub fn contacted(action: &str) -> Result<std::collections::BTreeSet<String>, SysexitsError> {
+    let path = state_dir().join("audit.jsonl");
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => {
+            tracing::error!("failed to read audit log `{}`: {e}", path.display());
+            return Err(EX_IOERR);
+        }
+    };
+
+    let mut contacted = std::collections::BTreeSet::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line.map_err(|e| {
+            tracing::error!("failed to read audit log `{}`: {e}", path.display());
+            EX_IOERR
+        })?;
+        let Ok(record) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        if record["action"].as_str() != Some(action) || record.get("error").is_some() {
+            continue;
+        }
+        if let Some(target) = record["target"].as_str() {
+            contacted.insert(audit_target(target));
+        }
+    }
+
+    Ok(contacted)
+}
+
+/// Normalizes a LinkedIn URL so that `https://www.linkedin.com/in/foo/` and
+/// `https://linkedin.com/in/foo` compare equal.
+#[cfg(feature = "write")]
+pub fn audit_target(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(url) => url.path().trim_end_matches('/').to_lowercase(),
+        Err(_) => url.trim().to_lowercase(),
+    }
+}
+
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        .read_timeout(Duration::from_secs(30))
+        .build()
+        .unwrap()
+}
+
+/// The exit code when LinkedIn requires a verification code to log in but
+/// none was provided.
+pub const EX_CODE_REQUIRED: SysexitsError = EX_NOUSER;
+
+/// The exit code when an action requires confirmation, but there is no
+/// terminal to ask on and `--yes` wasn't given. Distinct from
+/// [`EX_CODE_REQUIRED`] and the authentication failures, so that scheduled
+/// jobs can tell that they only lack `--yes`.
+pub const EX_CONFIRMATION_REQUIRED: SysexitsError = EX_NOHOST;
+
+/// The exit code when the user declined to confirm an action.
+pub const EX_DECLINED: SysexitsError = EX_NOPERM;
+
+/// The exit status section of the help of the programs that confirm their
+/// actions.
+pub const CONFIRMED_EXIT_STATUS: &str = "\
+Exit status:
+  0   (EX_OK)           all actions were performed, or there were none
+  64  (EX_USAGE)        an unsupported URL, invalid input, or a parameter
+                        rejected by the API
+  65  (EX_DATAERR)      an unparseable API response, or invalid template
+  66  (EX_NOINPUT)      a profile or conversation that doesn't exist
+  67  (EX_NOUSER)       a verification code is required to log in
+  68  (EX_NOHOST)       confirmation is required, but there is no terminal to
+                        ask on; pass --yes
+  69  (EX_UNAVAILABLE)  the API is unreachable, or out of credits or budget
+  75  (EX_TEMPFAIL)     rate limited, a server error or timeout; retry later
+  77  (EX_NOPERM)       the actions were not confirmed, or the API key, login
+                        token or login was rejected
+  78  (EX_CONFIG)       missing or invalid configuration";
+
+/// Options selecting the LinkedIn account and where its login token is stored.
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct AuthOptions {
+    /// The ASIMOV configuration profile with the credentials of the LinkedIn
+    /// account to use [default: `default`]
+    #[arg(value_name = "NAME", long)]
+    pub account: Option<String>,
+
+    /// Where to store the login token [default: the `token-store` variable,
+    /// or `keyring` if available, else `file`]
+    #[arg(value_name = "STORE", long, value_enum)]
+    pub token_store: Option<TokenStoreKind>,
+
+    /// The verification code, if LinkedIn requires one to log in
+    /// [default: the `linkedin-verification-code` variable]
+    #[arg(value_name = "CODE", long, group = "code")]
+    pub verification_code: Option<String>,
+
+    /// Read the verification code from a file when LinkedIn requires one
+    #[arg(value_name = "FILE", long, group = "code")]
+    pub verification_code_file: Option<PathBuf>,
+
+    /// Run a shell command printing the verification code when LinkedIn
+    /// requires one, e.g. a script reading it from a mailbox
+    #[arg(value_name = "COMMAND", long, group = "code")]
+    pub verification_code_command: Option<String>,
+
+    /// Log in again before a multi-page fetch once the login token is older
+    /// than this, e.g. `12h` [default: the `max-token-age` variable]
+    #[arg(value_name = "DURATION", long)]
+    pub max_token_age: Option<jiff::SignedDuration>,
+}
+
+/// Options limiting the Linkup API credits a program spends.
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct CreditOptions {
+    /// The most Linkup API credits to spend, stopping before a request that
+    /// would exceed them
+    #[arg(value_name = "COUNT", long)]
+    pub max_credits: Option<u32>,
+}
+
+impl CreditOptions {
+    /// Returns the credit budget for clients, if the credits are limited.
+    pub fn budget(&self) -> Option<CreditBudget> {
+        self.max_credits.map(CreditBudget::new)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum TokenStoreKind {
+    /// The operating system's keyring.
+    Keyring,
+    /// Encrypted files in `~/.asimov/configs/default/linkup/tokens`.
+    File,
+    /// Process memory only; the token is discarded on exit.
+    Memory,
+}
+
+/// Returns the module's configuration directory, `~/.asimov/configs/default/linkup`.
+pub fn config_dir() -> PathBuf {
+    asimov_env::paths::asimov_root()
+        .join("configs")
+        .join("default")
+        .join("linkup")
+}
+
+/// Reads a manifest variable of the selected account.
+pub fn variable(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+    name: &str,
+) -> Result<String, SysexitsError> {
+    manifest
+        .variable(name, options.account.as_deref())
+        .map_err(|e| {
+            tracing::error!("failed to read variable `{name}`: {e}");
+            EX_CONFIG
+        })
+}
+
+/// Reads a manifest variable of the selected account that may be left unset.
+pub fn optional_variable(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+    name: &str,
+) -> Result<Option<String>, SysexitsError> {
+    use asimov_module::ReadVarError;
+    match manifest.variable(name, options.account.as_deref()) {
+        Ok(value) => Ok(Some(value)),
+        Err(ReadVarError::UnknownVar(_) | ReadVarError::UnconfiguredVar(_)) => Ok(None),
+        Err(e) => {
+            tracing::error!("failed to read variable `{name}`: {e}");
+            Err(EX_CONFIG)
+        }
+    }
+}
+
+/// Reads a secret manifest variable of the selected account.
+///
+/// Unless the variable's environment variable (e.g. `LINKEDIN_PASSWORD`) is
+/// set, the secret may also be read from the file named by the `_FILE`
+/// variant (`LINKEDIN_PASSWORD_FILE`), or from the output of the shell
+/// command in the `_COMMAND` variant (`LINKEDIN_PASSWORD_COMMAND`). Secret
+/// values are never logged.
+pub fn secret_variable(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+    name: &str,
+) -> Result<SecretString, SysexitsError> {
+    match external_secret(manifest, name)? {
+        Some(secret) => Ok(secret),
+        None => variable(manifest, options, name).map(Into::into),
+    }
+}
+
+/// Reads a secret manifest variable of the selected account that may be left
+/// unset, as [`secret_variable`] does.
+pub fn optional_secret_variable(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+    name: &str,
+) -> Result<Option<SecretString>, SysexitsError> {
+    match external_secret(manifest, name)? {
+        Some(secret) => Ok(Some(secret)),
+        None => Ok(optional_variable(manifest, options, name)?.map(Into::into)),
+    }
+}
+
+/// Reads a secret from the `_FILE` or `_COMMAND` variant of a variable's
+/// environment variable, if the environment variable itself is unset.
+fn external_secret(
+    manifest: &ModuleManifest,
+    name: &str,
+) -> Result<Option<SecretString>, SysexitsError> {
+    let Some(env) = manifest
+        .config
+        .as_ref()
+        .and_then(|config| config.variables.iter().find(|var| var.name == name))
+        .and_then(|var| var.environment.as_deref())
+    else {
+        return Ok(None);
+    };
+
+    if std::env::var_os(env).is_some() {
+        return Ok(None);
+    }
+
+    let secret = if let Some(path) = std::env::var_os(std::format!("{env}_FILE")) {
+        let secret = std::fs::read_to_string(&path).map_err(|e| {
+            tracing::error!(
+                "failed to read `{env}_FILE` `{}`: {e}",
+                Path::new(&path).display()
+            );
+            EX_NOINPUT
+        })?;
+        secret.trim().into()
+    } else if let Ok(command) = std::env::var(std::format!("{env}_COMMAND")) {
+        // The command itself may contain secrets, so it isn't logged:
+        run_command(&command).map_err(|e| {
+            tracing::error!("failed to run `{env}_COMMAND`: {e}");
+            EX_UNAVAILABLE
+        })?
+    } else {
+        return Ok(None);
+    };
+
+    if secret.is_empty() {
+        tracing::error!("secret from `{env}_FILE` or `{env}_COMMAND` is empty");
+        return Err(EX_CONFIG);
+    }
+
+    Ok(Some(secret.into()))
+}
+
+/// Returns the email of the selected account, which keys its login token.
+pub fn account_email(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+) -> Result<String, SysexitsError> {
+    Ok(variable(manifest, options, "linkedin-email")?
+        .trim()
+        .to_lowercase())
+}
+
+/// Returns the token store selected by `--token-store` or the `token-store`
+/// manifest variable.
+pub fn token_store_kind(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+) -> Result<TokenStoreKind, SysexitsError> {
+    use clap::ValueEnum;
+
+    Ok(match options.token_store {
+        Some(kind) => kind,
+        None => match optional_variable(manifest, options, "token-store")? {
+            Some(name) => TokenStoreKind::from_str(&name, true).map_err(|_| {
+                tracing::error!("unknown token store `{name}`");
+                EX_CONFIG
+            })?,
+            None if cfg!(feature = "keyring") => TokenStoreKind::Keyring,
+            None => TokenStoreKind::File,
+        },
+    })
+}
+
+/// Opens the token store selected by `--token-store` or the `token-store`
+/// manifest variable.
+pub fn token_store(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+) -> Result<Box<dyn TokenStore>, SysexitsError> {
+    Ok(match token_store_kind(manifest, options)? {
+        #[cfg(feature = "keyring")]
+        TokenStoreKind::Keyring => Box::new(KeyringTokenStore::new("asimov-linkup-module")),
+        #[cfg(not(feature = "keyring"))]
+        TokenStoreKind::Keyring => {
+            tracing::error!("keyring token store is not available in this build");
+            return Err(EX_CONFIG);
+        }
+        TokenStoreKind::File => {
+            let dir = config_dir().join("tokens");
+            match optional_secret_variable(manifest, options, "token-passphrase")? {
+                Some(passphrase) => Box::new(FileTokenStore::with_passphrase(dir, passphrase)),
+                None => Box::new(FileTokenStore::with_key_file(
+                    dir,
+                    config_dir().join("tokens.key"),
+                )),
+            }
+        }
+        TokenStoreKind::Memory => Box::new(MemoryTokenStore::new()),
+    })
+}
+
+/// Returns the maximum login token age selected by `--max-token-age` or the
+/// `max-token-age` manifest variable, if any.
+pub fn max_token_age(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+) -> Result<Option<Duration>, SysexitsError> {
+    let age = match options.max_token_age {
+        Some(age) => age,
+        None => match optional_variable(manifest, options, "max-token-age")? {
+            Some(age) => age.parse().map_err(|e| {
+                tracing::error!("invalid maximum token age `{age}`: {e}");
+                EX_CONFIG
+            })?,
+            None => return Ok(None),
+        },
+    };
+    Duration::try_from(age).map(Some).map_err(|_| {
+        tracing::error!("the maximum token age must not be negative");
+        EX_CONFIG
+    })
+}
+
+pub fn token_store_error(e: TokenStoreError) -> SysexitsError {
+    tracing::error!("failed to access login token: {e}");
+    match e {
+        TokenStoreError::Io { .. } => EX_IOERR,
+        TokenStoreError::Decrypt(_) => EX_CONFIG,
+        TokenStoreError::InvalidData(_) | TokenStoreError::AccountMismatch { .. } => EX_DATAERR,
+        #[cfg(feature = "keyring")]
+        TokenStoreError::Keyring(_) => EX_UNAVAILABLE,
+    }
+}
+
+/// Builds a client for the selected account, with its saved login token if
+/// any. The client logs in with the account's credentials when needed,
+/// obtaining verification codes with [`verification_code`], and saves new
+/// login tokens to the selected token store.
+pub fn client(
+    http_client: &reqwest::Client,
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+) -> Result<Client, SysexitsError> {
+    let api_key = secret_variable(manifest, options, "linkup-api-key")?;
+    let credentials = Credentials::new(
+        variable(manifest, options, "linkedin-email")?,
+        secret_variable(manifest, options, "linkedin-password")?,
+    );
+
+    let token_store: Arc<dyn TokenStore> = token_store(manifest, options)?.into();
+    let saved_token = match token_store.load(&credentials.account()) {
+        // A token saved for another account must not be reused:
+        Err(e @ TokenStoreError::AccountMismatch { .. }) => {
+            tracing::warn!("discarding stored login token: {e}");
+            None
+        }
+        result => result.map_err(token_store_error)?,
+    };
+
+    let code_provider = CodeProvider {
+        manifest: manifest.clone(),
+        options: options.clone(),
+    };
+
+    Ok(Client::builder()
+        .api_key(api_key)
+        .maybe_login_token(saved_token)
+        .credentials(credentials)
+        .verification_code_provider(Arc::new(code_provider))
+        .token_store(token_store)
+        .maybe_max_token_age(max_token_age(manifest, options)?)
+        .http_client(http_client.clone())
+        .build())
+}
+
+/// Builds a client for the selected account, logging in right away if there
+/// is no saved login token.
+pub async fn connect(
+    http_client: &reqwest::Client,
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+) -> Result<Client, SysexitsError> {
+    let client = client(http_client, manifest, options)?;
+    if client.login_token().is_none() {
+        client.login().await.map_err(login_error)?;
+    }
+    Ok(client)
+}
+
+/// Maps a login failure to an exit code, after logging it.
+pub fn login_error(error: LoginError) -> SysexitsError {
+    tracing::error!("login failed: {error}");
+    match error {
+        LoginError::CodeRequired(_) => EX_CODE_REQUIRED,
+        LoginError::ApprovalTimeout(_) => {
+            tracing::error!("approve the sign-in in the LinkedIn app, then try again");
+            EX_TEMPFAIL
+        }
+        LoginError::CaptchaRequired(_) => {
+            tracing::error!(
+                "log in to LinkedIn in a browser from this network to clear the CAPTCHA, \
+                then try again"
+            );
+            EX_NOPERM
+        }
+        LoginError::UnsupportedChallenge(_) => {
+            tracing::error!(
+                "log in to LinkedIn in a browser to complete the challenge, then try again"
+            );
+            EX_NOPERM
+        }
+        LoginError::NoCredentials => EX_CONFIG,
+        LoginError::Request(e) | LoginError::Verify(VerifyError::Request(e)) => match e {
+            // The Linkup API rejected the credentials or verification code:
+            RequestError::Api { kind, .. } if !kind.is_retryable() => EX_NOPERM,
+            e => request_exit_code(&e),
+        },
+    }
+}
+
+/// Maps a failure to fetch a resource to an exit code, after logging it.
+pub fn fetch_error(error: FetchError) -> SysexitsError {
+    match error {
+        FetchError::Request(e) | FetchError::Partial { source: e, .. } => request_error(e),
+        error => {
+            tracing::error!("request failed: {error}");
+            match error {
+                FetchError::NotFound(_) => EX_NOINPUT,
+                _ => EX_USAGE,
+            }
+        }
+    }
+}
+
+/// Maps a failed write action to an exit code, after logging it, as
+/// [`request_error`] does for failed requests.
+#[cfg(feature = "write")]
+pub fn write_error(error: crate::error::WriteError) -> SysexitsError {
+    use crate::error::WriteError;
+    match error {
+        WriteError::Request(e) => request_error(e),
+        error => {
+            tracing::error!("request failed: {error}");
+            EX_USAGE
+        }
+    }
+}
+
+/// Returns a stable name for the kind of a fetch failure, for error records.
+pub fn fetch_error_kind(error: &FetchError) -> &'static str {
+    let Some(error) = error.request_error() else {
+        return match error {
+            FetchError::InvalidUrl(_) => "invalid_url",
+            FetchError::NotFound(_) => "not_found",
+            _ => "unsupported_url",
+        };
+    };
+    match error {
+        RequestError::Api { kind, .. } => match kind {
+            ApiErrorKind::InvalidToken => "invalid_token",
+            ApiErrorKind::InvalidApiKey => "invalid_api_key",
+            ApiErrorKind::InsufficientCredits => "insufficient_credits",
+            ApiErrorKind::RateLimited => "rate_limited",
+            ApiErrorKind::NotFound => "not_found",
+            ApiErrorKind::InvalidParameter => "invalid_parameter",
+            ApiErrorKind::Server => "server_error",
+            _ => "api_error",
+        },
+        RequestError::Http(_) => "network",
+        RequestError::InvalidJson(_) | RequestError::ParseError { .. } => "invalid_response",
+        RequestError::Login(_) => "login",
+        RequestError::BudgetExceeded { .. } => "budget_exceeded",
+    }
+}
+
+/// Maps a failed request to an exit code, after logging it.
+pub fn request_error(error: RequestError) -> SysexitsError {
+    match error {
+        RequestError::Login(e) => login_error(*e),
+        error => {
+            tracing::error!("request failed: {error}");
+            request_exit_code(&error)
+        }
+    }
+}
+
+/// Returns the exit code for a failed request:
+///
+/// - `EX_NOPERM` when the API key or login token was rejected,
+/// - `EX_TEMPFAIL` when rate limited, on server errors and network timeouts,
+/// - `EX_NOINPUT` when the resource doesn't exist,
+/// - `EX_USAGE` when a request parameter was rejected,
+/// - `EX_DATAERR` when the response couldn't be parsed,
+/// - `EX_UNAVAILABLE` otherwise, e.g. when out of credits.
+fn request_exit_code(error: &RequestError) -> SysexitsError {
+    match error {
+        RequestError::Api { kind, .. } => match kind {
+            ApiErrorKind::InvalidToken | ApiErrorKind::InvalidApiKey => EX_NOPERM,
+            ApiErrorKind::RateLimited | ApiErrorKind::Server => EX_TEMPFAIL,
+            ApiErrorKind::NotFound => EX_NOINPUT,
+            ApiErrorKind::InvalidParameter => EX_USAGE,
+            _ => EX_UNAVAILABLE,
+        },
+        RequestError::Http(_) if error.is_retryable() => EX_TEMPFAIL,
+        RequestError::Http(_) => EX_UNAVAILABLE,
+        RequestError::InvalidJson(_) | RequestError::ParseError { .. } => EX_DATAERR,
+        RequestError::Login(_) if error.is_retryable() => EX_TEMPFAIL,
+        RequestError::Login(_) => EX_NOPERM,
+        RequestError::BudgetExceeded { .. } => EX_UNAVAILABLE,
+    }
+}
+
+/// Obtains verification codes with [`verification_code`], and tells the
+/// user on stderr when the sign-in awaits approval.
+struct CodeProvider {
+    manifest: ModuleManifest,
+    options: AuthOptions,
+}
+
+impl crate::VerificationCodeProvider for CodeProvider {
+    fn verification_code(&self, message: &str) -> Option<SecretString> {
+        verification_code(&self.manifest, &self.options, message)
+            .ok()
+            .map(SecretString::from)
+    }
+
+    fn approval_pending(&self, message: &str) {
+        std::eprintln!("Approve the sign-in in the LinkedIn app to continue.");
+        std::eprintln!("LinkUp API response: `{message}`");
+    }
+}
+
+/// Obtains the verification code requested by `message` from the command-line
+/// options or the `linkedin-verification-code` variable, or else prompts for
+/// it on the terminal.
+///
+/// Fails with [`EX_CODE_REQUIRED`] when no code is available and there is
+/// no terminal to prompt on.
+pub fn verification_code(
+    manifest: &ModuleManifest,
+    options: &AuthOptions,
+    message: &str,
+) -> Result<String, SysexitsError> {
+    use std::io::IsTerminal;
+
+    tracing::info!("verification code required: {message}");
+
+    let code = if let Some(code) = &options.verification_code {
+        code.clone()
+    } else if let Some(path) = &options.verification_code_file {
+        std::fs::read_to_string(path).map_err(|e| {
+            tracing::error!(
+                "failed to read verification code from `{}`: {e}",
+                path.display()
+            );
+            EX_CODE_REQUIRED
+        })?
+    } else if let Some(command) = &options.verification_code_command {
+        run_command(command).map_err(|e| {
+            tracing::error!("failed to obtain verification code from command: {e}");
+            EX_CODE_REQUIRED
+        })?
+    } else if let Some(code) =
+        optional_secret_variable(manifest, options, "linkedin-verification-code")?
+    {
+        code.expose_secret().into()
+    } else if std::io::stdin().is_terminal() {
+        return prompt_verification_code(message);
+    } else {
+        tracing::error!(
+            "verification code required, but none was provided with `--verification-code`, \
+            `--verification-code-file`, `--verification-code-command` or `LINKEDIN_VERIFICATION_CODE`"
+        );
+        return Err(EX_CODE_REQUIRED);
+    };
+
+    let code = code.trim();
+    if code.is_empty() {
+        tracing::error!("verification code is empty");
+        return Err(EX_CODE_REQUIRED);
+    }
+    Ok(code.into())
+}
+
+fn prompt_verification_code(message: &str) -> Result<String, SysexitsError> {
+    let mut stderr = std::io::stderr().lock();
+    let mut stdin = std::io::stdin().lines();
+
+    stderr.write_all(b"Verification code required.\n").unwrap();
+    std::writeln!(&mut stderr, "LinkUp API response: `{message}`").unwrap();
+    stderr.write_all(b"Enter code:\n").unwrap();
+
+    loop {
+        stderr.write_all(b"> ").unwrap();
+        stderr.flush().unwrap();
+
+        match stdin.next() {
+            Some(Ok(code)) if !code.trim().is_empty() => break Ok(code.trim().into()),
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => {
+                tracing::error!("error while reading input: {e}");
+                break Err(EX_IOERR);
+            }
+            None => {
+                tracing::error!("verification code is required");
+                break Err(EX_CODE_REQUIRED);
+            }
+        }
+    }
+}
+
+/// Runs a shell command, returning its trimmed standard output.
+pub fn run_command(command: &str) -> std::io::Result<String> {
+    #[cfg(windows)]
+    let output = std::process::Command::new("cmd")
+        .args(["/C", command])
+        .stderr(std::process::Stdio::inherit())
+        .output()?;
+    #[cfg(not(windows))]
+    let output = std::process::Command::new("sh")
+        .args(["-c", command])
+        .stderr(std::process::Stdio::inherit())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(std::format!(
+            "command failed with {}",
+            output.status
+        )));
+    }
+
+    String::from_utf8(output.stdout)
+        .map(|stdout| stdout.trim().into())
+        .map_err(std::io::Error::other)
+}
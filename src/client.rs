@@ -1,9 +1,12 @@
 // This is free and unencumbered software released into the public domain.
 
 use std::string::String;
+use std::sync::Arc;
 use std::vec::Vec;
 
+use crate::TokenStore;
 use asimov_module::secrecy::{ExposeSecret, SecretString};
+use jiff::Timestamp;
 use serde_json::json;
 
 mod api;
@@ -12,9 +15,32 @@ use api::V1_API_URL;
 pub mod error;
 use error::*;
 
+mod credits;
+pub use credits::*;
+
+mod page_size;
+pub use page_size::*;
+
+mod pagination;
+pub use pagination::*;
+
+mod rate_limit;
+pub use rate_limit::*;
+
+mod session;
+pub use session::*;
+
 mod types;
 pub use types::*;
 
+#[cfg(feature = "write")]
+mod write;
+#[cfg(feature = "write")]
+pub use write::*;
+
+/// The country sent with every request, and recorded with login tokens.
+pub(crate) const COUNTRY: &str = "US";
+
 #[tracing::instrument(skip_all)]
 pub async fn login(
     client: &reqwest::Client,
@@ -25,7 +51,7 @@ pub async fn login(
     let request = json!({
         "email": email.expose_secret(),
         "password": password.expose_secret(),
-        "country": "US",
+        "country": COUNTRY,
     });
 
     let api_url = std::format!("{V1_API_URL}/auth/login");
@@ -37,22 +63,13 @@ pub async fn login(
         .header("x-api-key", api_key.expose_secret())
         .json(&request)
         .send()
-        .await?
-        .error_for_status()?;
+        .await?;
 
-    let status = response.status();
-    let body = response.text().await?;
+    let (status, body) = read_response(response).await?;
 
     match serde_json::from_str::<api::LoginResponse>(&body) {
-        Ok(api::LoginResponse::Success { success }) => match success {
-            api::LoginResponseType::WithToken { login_token, .. } => {
-                Ok(LoginResult::GotToken { login_token })
-            }
-            api::LoginResponseType::NeedCode { message, .. } => {
-                Ok(LoginResult::NeedCode { message })
-            }
-        },
-        Ok(api::LoginResponse::Error { message }) => Err(RequestError::Api(message).into()),
+        Ok(api::LoginResponse::Success { success }) => Ok(success.into()),
+        Ok(api::LoginResponse::Error { message }) => Err(RequestError::api(status, message).into()),
         Err(err) => {
             tracing::error!(?err, ?body, ?status, "failed to parse response");
             Err(RequestError::ParseError { status, body }.into())
@@ -70,7 +87,7 @@ pub async fn verify(
     let request = json!({
         "email": email.expose_secret(),
         "code": code.expose_secret(),
-        "country": "US",
+        "country": COUNTRY,
     });
 
     let api_url = std::format!("{V1_API_URL}/auth/verify");
@@ -82,15 +99,15 @@ pub async fn verify(
         .header("x-api-key", api_key.expose_secret())
         .json(&request)
         .send()
-        .await?
-        .error_for_status()?;
+        .await?;
 
-    let status = response.status();
-    let body = response.text().await?;
+    let (status, body) = read_response(response).await?;
 
     match serde_json::from_str::<api::VerifyResponse>(&body) {
         Ok(api::VerifyResponse::Success { login_token, .. }) => Ok(login_token),
-        Ok(api::VerifyResponse::Error { message, .. }) => Err(RequestError::Api(message).into()),
+        Ok(api::VerifyResponse::Error { message, .. }) => {
+            Err(RequestError::api(status, message).into())
+        }
         Err(err) => {
             tracing::error!(?err, ?status, ?body, "failed to parse response");
             Err(RequestError::ParseError { status, body }.into())
@@ -98,48 +115,162 @@ pub async fn verify(
     }
 }
 
-#[derive(Clone, Debug, bon::Builder)]
+#[derive(Clone, bon::Builder)]
 #[builder(on(SecretString, into))]
 pub struct Client {
     #[builder(default)]
     pub http_client: reqwest::Client,
     pub api_key: SecretString,
-    pub login_token: SecretString,
+    /// The login token, shared by clones of the client. Without one, the
+    /// client logs in with its credentials on the first request.
+    #[builder(
+        setters(name = login_token),
+        with = |token: impl Into<LoginToken>| Session::new(token.into()),
+        default
+    )]
+    session: Arc<Session>,
+    /// The credentials to log in with when there is no login token or it
+    /// expired.
+    pub credentials: Option<Credentials>,
+    /// Provides the verification code when LinkedIn requires one to log in.
+    pub verification_code_provider: Option<Arc<dyn VerificationCodeProvider>>,
+    /// Where to save the login token after logging in.
+    pub token_store: Option<Arc<dyn TokenStore>>,
+    pub rate_limiter: Option<RateLimiter>,
+    /// How old the login token may get before the client logs in again
+    /// ahead of a multi-page fetch, rather than risk it expiring midway.
+    pub max_token_age: Option<core::time::Duration>,
+    /// The most pages fetched of a listing, in case the API never stops
+    /// returning a next page.
+    #[builder(default = DEFAULT_MAX_PAGES)]
+    pub max_pages: u32,
+    /// The page sizes to request from paginated endpoints, tuned down for the
+    /// session when the API fails with larger ones.
+    #[builder(default)]
+    pub page_sizes: PageSizes,
+    /// The most credits to spend, failing requests that would exceed it.
+    pub credit_budget: Option<CreditBudget>,
+}
+
+impl core::fmt::Debug for Client {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Client")
+            .field("http_client", &self.http_client)
+            .field("api_key", &self.api_key)
+            .field("credentials", &self.credentials)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("max_token_age", &self.max_token_age)
+            .field("max_pages", &self.max_pages)
+            .field("page_sizes", &self.page_sizes)
+            .field("credit_budget", &self.credit_budget)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Client {
     #[tracing::instrument(skip(self), fields(url = url.as_ref()))]
     pub async fn fetch(&self, url: impl AsRef<str>) -> Result<serde_json::Value, FetchError> {
-        let url = url::Url::try_from(url.as_ref())?;
+        self.fetch_from(url, None).await
+    }
 
-        if url
-            .host_str()
-            .is_none_or(|host| !host.ends_with("linkedin.com"))
-        {
-            return Err(FetchError::UnknownResource(url.into()));
+    /// Fetches the resource at `url` as [`fetch`](Self::fetch) does, resuming
+    /// a listing from the page or cursor where a [`FetchError::Partial`]
+    /// left off. A resume point not matching the resource is ignored.
+    #[tracing::instrument(skip(self), fields(url = url.as_ref()))]
+    pub async fn fetch_from(
+        &self,
+        url: impl AsRef<str>,
+        resume: Option<&ResumePoint>,
+    ) -> Result<serde_json::Value, FetchError> {
+        match Resource::parse(url.as_ref())? {
+            Resource::Profile(url) => self.fetch_profile(&url).await,
+            Resource::Company(url) => self.fetch_company(&url).await,
+            Resource::Listing(listing) => self
+                .fetch_listing(&listing, resume, None)
+                .await
+                .map(serde_json::Value::Array),
         }
+    }
 
-        let path = url.path();
-        if path.starts_with("/in/") {
-            return self.fetch_profile(&url).await;
-        }
-        if path.starts_with("/company/") {
-            return self.fetch_company(&url).await;
-        }
-        if path.starts_with("/messaging/thread/") {
-            return self
-                .fetch_conversation(&url)
+    /// Fetches only what changed since `since` in the inbox or conversation at
+    /// `url`: the conversations active since, or the messages sent since.
+    ///
+    /// Inbox pagination stops at the first conversation last active before
+    /// `since`, and conversations not active since aren't fetched at all.
+    /// Other resources are fetched in full.
+    #[tracing::instrument(skip(self), fields(url = url.as_ref()))]
+    pub async fn fetch_since(
+        &self,
+        url: impl AsRef<str>,
+        since: Timestamp,
+    ) -> Result<serde_json::Value, FetchError> {
+        match Resource::parse(url.as_ref())? {
+            Resource::Profile(url) => self.fetch_profile(&url).await,
+            Resource::Company(url) => self.fetch_company(&url).await,
+            Resource::Listing(listing) => self
+                .fetch_listing(&listing, None, Some(since))
                 .await
-                .map(serde_json::Value::Array);
-        }
-        if path.starts_with("/messaging") {
-            return self.fetch_inbox().await.map(serde_json::Value::Array);
-        }
-        if path.starts_with("/mynetwork/invite-connect/connections") {
-            return self.fetch_connections().await.map(serde_json::Value::Array);
+                .map(serde_json::Value::Array),
         }
+    }
 
-        return Err(FetchError::UnknownResource(url.into()));
+    /// Fetches a single page of the listing at `url`, the first one or the
+    /// one at `resume`, along with where the next page starts. This allows
+    /// callers to process and checkpoint long listings page by page.
+    ///
+    /// A profile or company is returned as the only item of a last page.
+    #[tracing::instrument(skip(self), fields(url = url.as_ref()))]
+    pub async fn fetch_page(
+        &self,
+        url: impl AsRef<str>,
+        resume: Option<&ResumePoint>,
+    ) -> Result<Page, FetchError> {
+        self.fetch_page_since(url, resume, None).await
+    }
+
+    /// Fetches a single page of the listing at `url` as
+    /// [`fetch_page`](Self::fetch_page) does, with only what changed since
+    /// `since`, if given, as [`fetch_since`](Self::fetch_since) does.
+    #[tracing::instrument(skip(self), fields(url = url.as_ref()))]
+    pub async fn fetch_page_since(
+        &self,
+        url: impl AsRef<str>,
+        resume: Option<&ResumePoint>,
+        since: Option<Timestamp>,
+    ) -> Result<Page, FetchError> {
+        let item = match Resource::parse(url.as_ref())? {
+            Resource::Profile(url) => self.fetch_profile(&url).await?,
+            Resource::Company(url) => self.fetch_company(&url).await?,
+            Resource::Listing(listing) => {
+                self.refresh_if_stale().await?;
+                return self.fetch_listing_page(&listing, resume, since).await;
+            }
+        };
+        Ok(Page {
+            items: std::vec![item],
+            next: None,
+        })
+    }
+
+    /// Estimates the API calls and credits fetching the resource at `url`
+    /// takes with this client, from `resume` on if given, without making any
+    /// requests. See [`Estimate::of`].
+    pub fn estimate(
+        &self,
+        url: impl AsRef<str>,
+        resume: Option<&ResumePoint>,
+    ) -> Result<Estimate, FetchError> {
+        Estimate::of(url.as_ref(), resume, &self.page_sizes, self.max_pages)
+    }
+
+    /// Returns a guard for fetching the listing at `url` page by page with
+    /// [`fetch_page`](Self::fetch_page), stopping at pagination loops and
+    /// [`max_pages`](Self::max_pages), and dropping duplicate items.
+    pub fn pagination(&self, url: impl AsRef<str>) -> Pagination {
+        match Resource::parse(url.as_ref()) {
+            Ok(Resource::Listing(listing)) => listing.pagination(self.max_pages),
+            _ => Pagination::new(self.max_pages),
+        }
     }
 
     #[tracing::instrument(skip_all)]
@@ -149,258 +280,375 @@ impl Client {
             .replace("https://linkedin.com", "https://www.linkedin.com");
         let request = json!({
             "company_url": url,
-            "country": "US",
-            "login_token": self.login_token.expose_secret(),
         });
 
-        let api_url = std::format!("{V1_API_URL}/companies/info");
+        tracing::debug!(linkedin_url = url.as_str(), "Requesting company...");
 
-        tracing::debug!(linkedin_url = url.as_str(), url = api_url, "Requesting...");
-
-        let response = self
-            .http_client
-            .post(api_url)
-            .header("x-api-key", self.api_key.expose_secret())
-            .json(&request)
-            .send()
-            .await?
-            .error_for_status()?;
+        Ok(self.request("/companies/info", request).await?)
+    }
 
-        let status = response.status();
-        let body = response.text().await?;
+    /// Fetches all of your LinkedIn connections.
+    #[tracing::instrument(skip_all)]
+    pub async fn fetch_connections(&self) -> Result<Vec<serde_json::Value>, FetchError> {
+        self.fetch_listing(&Listing::CONNECTIONS, None, None).await
+    }
 
-        match serde_json::from_str::<api::FetchResponse>(&body) {
-            Ok(api::FetchResponse::Success { data, .. }) => Ok(data),
-            Ok(api::FetchResponse::Error { message, .. }) => Err(RequestError::Api(message).into()),
-            Err(err) => {
-                tracing::error!(?err, ?status, ?body, "failed to parse response");
-                Err(RequestError::ParseError { status, body }.into())
-            }
-        }
+    /// Fetches all conversations in your LinkedIn messaging inbox.
+    #[tracing::instrument(skip_all)]
+    pub async fn fetch_inbox(&self) -> Result<Vec<serde_json::Value>, FetchError> {
+        self.fetch_inbox_from(None).await
     }
 
+    /// Fetches the conversations in your LinkedIn messaging inbox from the
+    /// page at `cursor` on, or from the first page.
     #[tracing::instrument(skip_all)]
-    async fn fetch_conversation(
+    pub async fn fetch_inbox_from(
         &self,
-        url: &url::Url,
+        cursor: Option<&str>,
     ) -> Result<Vec<serde_json::Value>, FetchError> {
-        // take id from /messaging/thread/:id
-        let id = url.path_segments().unwrap().nth(2).unwrap();
-
-        let conv_id = self
-            .find_conversation(id)
-            .await?
-            .ok_or_else(|| FetchError::UnknownResource(url.as_str().into()))?;
+        let resume = cursor.map(|cursor| ResumePoint::Cursor(cursor.into()));
+        self.fetch_listing(&Listing::Inbox, resume.as_ref(), None)
+            .await
+    }
 
-        let mut all_messages = Vec::new();
-        let mut start_page = 1;
+    /// Fetches all items of a listing, from `resume` on or from the start,
+    /// and only those changed since `since` if given.
+    ///
+    /// Fails with [`FetchError::Partial`] if a request fails after some pages
+    /// were fetched.
+    async fn fetch_listing(
+        &self,
+        listing: &Listing,
+        resume: Option<&ResumePoint>,
+        since: Option<Timestamp>,
+    ) -> Result<Vec<serde_json::Value>, FetchError> {
+        self.refresh_if_stale().await?;
 
-        let batch_end_page_offset = 9;
+        let mut all_items = Vec::new();
+        let mut resume = resume.cloned();
+        let mut progressed = false;
+        let mut pagination = listing.pagination(self.max_pages);
 
         loop {
-            let request = json!({
-                "conversation_id": conv_id,
-                "login_token": self.login_token.expose_secret(),
-                "country": "US",
-                "start_page": start_page,
-                "end_page": start_page + batch_end_page_offset
-            });
-
-            let api_url = std::format!("{V1_API_URL}/messages/conversation");
-
-            tracing::debug!(
-                url = api_url,
-                page = start_page,
-                "Requesting conversation messages..."
-            );
-
-            let response = self
-                .http_client
-                .post(api_url)
-                .header("x-api-key", self.api_key.expose_secret())
-                .json(&request)
-                .send()
-                .await?
-                .error_for_status()?;
-
-            let status = response.status();
-            let body = response.text().await?;
-
-            tracing::debug!(body);
-
-            match serde_json::from_str::<api::FetchResponse>(&body) {
-                Ok(api::FetchResponse::Success { data }) => {
-                    let Some(messages) = data["messages"].as_array() else {
-                        break;
-                    };
-
-                    all_messages.extend_from_slice(messages);
-
-                    if data["pagination"]["messages_per_page"]
-                        .as_u64()
-                        .is_none_or(|per_page| messages.len() < per_page as usize)
-                    {
-                        break;
-                    };
-
-                    start_page += batch_end_page_offset + 1;
-                }
-                Ok(api::FetchResponse::Error { message }) => {
-                    return Err(RequestError::Api(message).into());
+            match self
+                .fetch_listing_page(listing, resume.as_ref(), since)
+                .await
+            {
+                Ok(page) => {
+                    let page = pagination.check(page);
+                    all_items.extend(page.items);
+                    match page.next {
+                        Some(next) => resume = Some(next),
+                        None => return Ok(all_items),
+                    }
+                    progressed = true;
                 }
-                Err(err) => {
-                    tracing::error!(?err, ?status, ?body, "failed to parse response");
-                    return Err(RequestError::ParseError { status, body }.into());
+                Err(FetchError::Request(source)) if progressed => {
+                    tracing::warn!(?resume, "fetched {} items before failing", all_items.len());
+                    return Err(FetchError::Partial {
+                        items: all_items,
+                        resume: resume.unwrap(),
+                        source,
+                    });
                 }
+                Err(e) => return Err(e),
             }
         }
-
-        Ok(all_messages)
     }
 
-    #[tracing::instrument(skip_all)]
-    async fn fetch_connections(&self) -> Result<Vec<serde_json::Value>, FetchError> {
-        let mut all_connections = Vec::new();
-        let mut start_page = 1;
-
-        let batch_end_page_offset = 9;
+    /// Fetches the page of a listing at `resume`, or its first page, with
+    /// only the conversations or messages since `since` if given.
+    async fn fetch_listing_page(
+        &self,
+        listing: &Listing,
+        resume: Option<&ResumePoint>,
+        since: Option<Timestamp>,
+    ) -> Result<Page, FetchError> {
+        match listing {
+            Listing::Pages { endpoint, field } => {
+                let start_page = match resume {
+                    Some(ResumePoint::Page(page)) => *page,
+                    _ => 1,
+                };
+                tracing::debug!(page = start_page, "Requesting {field}...");
+
+                let (data, pages) = self
+                    .request_sized(endpoint, resume.is_some(), |pages| {
+                        json!({
+                            "start_page": start_page,
+                            "end_page": start_page + pages - 1,
+                        })
+                    })
+                    .await?;
+
+                let items = match data[field].as_array() {
+                    Some(items)
+                        if !items.is_empty()
+                            && data["total_results"]
+                                .as_u64()
+                                .is_none_or(|total| total != 0) =>
+                    {
+                        items.clone()
+                    }
+                    _ => return Ok(Page::default()),
+                };
 
-        loop {
-            let request = json!({
-                "login_token": self.login_token.expose_secret(),
-                "country": "US",
-                "start_page": start_page,
-                "end_page": start_page + batch_end_page_offset,
-            });
+                Ok(Page {
+                    items,
+                    next: Some(ResumePoint::Page(start_page + pages)),
+                })
+            }
 
-            let api_url = std::format!("{V1_API_URL}/network/connections");
-
-            tracing::debug!(
-                url = api_url,
-                page = start_page,
-                "Requesting connections..."
-            );
-
-            let response = self
-                .http_client
-                .post(api_url)
-                .header("x-api-key", self.api_key.expose_secret())
-                .json(&request)
-                .send()
-                .await?
-                .error_for_status()?;
-
-            let status = response.status();
-            let body = response.text().await?;
-
-            match serde_json::from_str::<api::FetchResponse>(&body) {
-                Ok(api::FetchResponse::Success { data }) => {
-                    let Some(connections) = data["connections"].as_array() else {
-                        break;
-                    };
-
-                    if connections.is_empty() {
-                        break;
+            Listing::Inbox => {
+                let cursor = match resume {
+                    Some(ResumePoint::Cursor(cursor)) => Some(cursor.as_str()),
+                    _ => None,
+                };
+
+                tracing::debug!(cursor, "Requesting inbox...");
+
+                let inbox_data = self.fetch_inbox_page(cursor).await?;
+                let mut page = Page {
+                    items: inbox_data.conversations,
+                    next: inbox_data.next_cursor.map(ResumePoint::Cursor),
+                };
+
+                // The inbox lists the most recently active conversations
+                // first, so the rest are older still:
+                if let Some(since) = since {
+                    let count = page.items.len();
+                    page.items.retain(|conversation| {
+                        activity_time(conversation).is_none_or(|at| at > since)
+                    });
+                    if page.items.len() < count {
+                        page.next = None;
                     }
+                }
 
-                    if data["total_results"]
-                        .as_u64()
-                        .is_some_and(|total| total == 0)
-                    {
-                        break;
-                    }
+                Ok(page)
+            }
 
-                    all_connections.extend_from_slice(connections);
+            Listing::Conversation(url) => {
+                let (conversation_id, start_page) = match resume {
+                    Some(ResumePoint::Conversation {
+                        conversation_id,
+                        page,
+                    }) => (conversation_id.clone(), *page),
+                    _ => {
+                        // take id from /messaging/thread/:id
+                        let id = url.path_segments().unwrap().nth(2).unwrap();
+                        let conversation = self
+                            .find_conversation(id)
+                            .await?
+                            .ok_or_else(|| FetchError::NotFound(url.as_str().into()))?;
+
+                        // Skip the messages of conversations not active since:
+                        match (since, activity_time(&conversation)) {
+                            (Some(since), Some(active)) if active <= since => {
+                                return Ok(Page::default());
+                            }
+                            _ => {}
+                        }
 
-                    start_page += batch_end_page_offset + 1;
-                }
-                Ok(api::FetchResponse::Error { message }) => {
-                    return Err(RequestError::Api(message).into());
-                }
-                Err(err) => {
-                    tracing::error!(?err, ?status, ?body, "failed to parse response");
-                    return Err(RequestError::ParseError { status, body }.into());
+                        let conversation_id = conversation["conversation_id"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .into();
+                        (conversation_id, 1)
+                    }
+                };
+
+                tracing::debug!(page = start_page, "Requesting conversation messages...");
+
+                let (data, pages) = self
+                    .request_sized("/messages/conversation", start_page > 1, |pages| {
+                        json!({
+                            "conversation_id": conversation_id,
+                            "start_page": start_page,
+                            "end_page": start_page + pages - 1,
+                        })
+                    })
+                    .await?;
+
+                let Some(messages) = data["messages"].as_array() else {
+                    return Ok(Page::default());
+                };
+
+                let mut last = data["pagination"]["messages_per_page"]
+                    .as_u64()
+                    .is_none_or(|per_page| messages.len() < per_page as usize);
+
+                let mut messages = messages.clone();
+                if let Some(since) = since {
+                    let count = messages.len();
+                    let times: Vec<_> = messages.iter().map(activity_time).collect();
+                    // Later pages only have older messages if this one lists
+                    // the newest first:
+                    let newest_first = matches!(
+                        (times.first(), times.last()),
+                        (Some(Some(first)), Some(Some(last))) if first > last
+                    );
+                    let mut times = times.into_iter();
+                    messages.retain(|_| times.next().flatten().is_none_or(|at| at > since));
+                    if newest_first && messages.len() < count {
+                        last = true;
+                    }
                 }
+
+                Ok(Page {
+                    items: messages,
+                    next: (!last).then(|| ResumePoint::Conversation {
+                        conversation_id,
+                        page: start_page + pages,
+                    }),
+                })
             }
         }
+    }
 
-        Ok(all_connections)
+    /// Checks whether the login token is still accepted, by requesting the
+    /// first conversation of the inbox. Never logs in.
+    #[tracing::instrument(skip_all)]
+    pub async fn check_login(&self) -> Result<bool, RequestError> {
+        let Some(token) = self.session.token() else {
+            return Ok(false);
+        };
+
+        match self
+            .send("/messages/inbox", json!({ "total_results": 1 }), &token)
+            .await
+        {
+            Ok(_) => {
+                self.mark_validated(&token);
+                Ok(true)
+            }
+            Err(e) if e.is_session_expired() => Ok(false),
+            Err(e) => Err(e),
+        }
     }
 
+    /// Fetches a LinkedIn profile (`/in/:account`).
     #[tracing::instrument(skip_all)]
-    async fn fetch_inbox(&self) -> Result<Vec<serde_json::Value>, FetchError> {
-        let mut all_conversations = Vec::new();
+    pub async fn fetch_profile(&self, url: &url::Url) -> Result<serde_json::Value, FetchError> {
+        let request = json!({
+            "linkedin_url": url.as_str(),
+        });
+
+        tracing::debug!(linkedin_url = url.as_str(), "Requesting profile...");
+
+        Ok(self.request("/profile/info", request).await?)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_conversation(&self, id: &str) -> Result<Option<serde_json::Value>, FetchError> {
         let mut next_cursor: Option<String> = None;
+        let mut pagination = Listing::Inbox.pagination(self.max_pages);
 
         loop {
-            let mut request = json!({
-                "login_token": self.login_token.expose_secret(),
-                "country": "US",
-                // API doesn't accept bigger values? will return `"data":[]` in response which also breaks parsing
-                "total_results": 25,
+            tracing::debug!(cursor = ?next_cursor, "Requesting inbox...");
+
+            let inbox_data = self.fetch_inbox_page(next_cursor.as_deref()).await?;
+            let page = pagination.check(Page {
+                items: inbox_data.conversations,
+                next: inbox_data.next_cursor.map(ResumePoint::Cursor),
             });
 
-            if let Some(cursor) = &next_cursor {
-                request["next_cursor"] = json!(cursor);
+            if let Some(conv) = matching_conversation(page.items, id) {
+                return Ok(Some(conv));
             }
 
-            let api_url = std::format!("{V1_API_URL}/messages/inbox");
-
-            tracing::debug!(url = api_url, cursor = ?next_cursor, "Requesting inbox...");
-
-            let response = self
-                .http_client
-                .post(api_url)
-                .header("x-api-key", self.api_key.expose_secret())
-                .json(&request)
-                .send()
-                .await?
-                .error_for_status()?;
-
-            let status = response.status();
-            let body = response.text().await?;
-
-            match serde_json::from_str::<api::FetchResponse>(&body) {
-                Ok(api::FetchResponse::Success { data }) => {
-                    let inbox_data: api::InboxData =
-                        serde_json::from_value(data).map_err(|err| {
-                            tracing::error!(?err, "failed to parse inbox data");
-                            RequestError::InvalidJson(err)
-                        })?;
-
-                    all_conversations.extend(inbox_data.conversations);
-
-                    // Check if there are more pages
-                    if let Some(cursor) = inbox_data.next_cursor {
-                        next_cursor = Some(cursor);
-                    } else {
-                        break;
-                    }
-                }
-                Ok(api::FetchResponse::Error { message }) => {
-                    return Err(RequestError::Api(message).into());
-                }
-                Err(err) => {
-                    tracing::error!(?err, ?status, ?body, "failed to parse response");
-                    return Err(RequestError::ParseError { status, body }.into());
+            // Check if there are more pages
+            match page.next {
+                Some(ResumePoint::Cursor(cursor)) => next_cursor = Some(cursor),
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    async fn fetch_inbox_page(&self, cursor: Option<&str>) -> Result<api::InboxData, RequestError> {
+        // Too many results make the API return `"data":[]` instead, but a
+        // later page can't have too many when the first didn't:
+        let (data, _) = self
+            .request_sized(INBOX_ENDPOINT, cursor.is_some(), |total_results| {
+                let mut request = json!({
+                    "total_results": total_results,
+                });
+                if let Some(cursor) = cursor {
+                    request["next_cursor"] = json!(cursor);
                 }
+                request
+            })
+            .await?;
+
+        serde_json::from_value(data).map_err(|err| {
+            tracing::error!(?err, "failed to parse inbox data");
+            RequestError::InvalidJson(err)
+        })
+    }
+
+    /// Posts `request` to the given Linkup API endpoint, adding the login
+    /// token and country, and returns the `data` of a successful response.
+    ///
+    /// When the client has credentials, it logs in if it has no login token
+    /// yet, and logs in again and retries if the login token expired.
+    async fn request(
+        &self,
+        endpoint: &str,
+        request: serde_json::Value,
+    ) -> Result<serde_json::Value, RequestError> {
+        let token = match self.session.token() {
+            Some(token) => token,
+            None => self.refresh(None).await?,
+        };
+
+        match self.send(endpoint, request.clone(), &token).await {
+            Err(e) if e.is_session_expired() && self.credentials.is_some() => {
+                tracing::info!("login token expired, logging in again: {e}");
+                let token = self.refresh(Some(&token)).await?;
+                self.send(endpoint, request, &token).await
             }
+            result => result,
         }
+    }
 
-        Ok(all_conversations)
+    /// Requests a page of `endpoint` of the size the API currently accepts,
+    /// backing off to smaller sizes as [`PageSizes::request`] does, and
+    /// returns the data along with the size requested. A page that
+    /// `continues` a listing whose previous page returned data is only
+    /// retried on timeouts, as empty data is then the end of the listing.
+    async fn request_sized(
+        &self,
+        endpoint: &'static str,
+        continues: bool,
+        request: impl Fn(u32) -> serde_json::Value,
+    ) -> Result<(serde_json::Value, u32), RequestError> {
+        self.page_sizes
+            .request(endpoint, continues, |size| {
+                self.request(endpoint, request(size))
+            })
+            .await
     }
 
-    #[tracing::instrument(skip_all)]
-    async fn fetch_profile(&self, url: &url::Url) -> Result<serde_json::Value, FetchError> {
-        let request = json!({
-            "linkedin_url": url.as_str(),
-            "country": "US",
-            "login_token": self.login_token.expose_secret(),
-        });
+    async fn send(
+        &self,
+        endpoint: &str,
+        mut request: serde_json::Value,
+        login_token: &SecretString,
+    ) -> Result<serde_json::Value, RequestError> {
+        if let Some(budget) = &self.credit_budget {
+            budget.charge(CreditCost::of_request(endpoint, &request))?;
+        }
 
-        let api_url = std::format!("{V1_API_URL}/profile/info");
+        request["login_token"] = json!(login_token.expose_secret());
+        request["country"] = json!(COUNTRY);
 
-        tracing::debug!(linkedin_url = url.as_str(), url = api_url, "Requesting...");
+        let api_url = std::format!("{V1_API_URL}{endpoint}");
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.wait().await;
+        }
+
+        tracing::debug!(url = api_url, "Requesting...");
 
         let response = self
             .http_client
@@ -408,86 +656,230 @@ impl Client {
             .header("x-api-key", self.api_key.expose_secret())
             .json(&request)
             .send()
-            .await?
-            .error_for_status()?;
+            .await?;
 
-        let status = response.status();
-        let body = response.text().await?;
+        let (status, body) = read_response(response).await?;
 
         match serde_json::from_str::<api::FetchResponse>(&body) {
-            Ok(api::FetchResponse::Success { data, .. }) => Ok(data),
-            Ok(api::FetchResponse::Error { message, .. }) => Err(RequestError::Api(message).into()),
+            Ok(api::FetchResponse::Success { data }) => Ok(data),
+            Ok(api::FetchResponse::Error { message }) => Err(RequestError::api(status, message)),
             Err(err) => {
                 tracing::error!(?err, ?status, ?body, "failed to parse response");
-                Err(RequestError::ParseError { status, body }.into())
+                Err(RequestError::ParseError { status, body })
             }
         }
     }
+}
 
-    #[tracing::instrument(skip(self))]
-    async fn find_conversation(&self, id: &str) -> Result<Option<String>, FetchError> {
-        let mut next_cursor: Option<String> = None;
-
-        loop {
-            let mut request = json!({
-                "login_token": self.login_token.expose_secret(),
-                "country": "US",
-                // API doesn't accept bigger values? will return `"data":[]` in response which also breaks parsing
-                "total_results": 25,
-            });
+/// Returns the status and body of a Linkup API response, or the API error
+/// of a response with an error status.
+async fn read_response(
+    response: reqwest::Response,
+) -> Result<(reqwest::StatusCode, String), RequestError> {
+    let status = response.status();
+    let body = response.text().await?;
 
-            if let Some(cursor) = &next_cursor {
-                request["next_cursor"] = json!(cursor);
-            }
+    if status.is_client_error() || status.is_server_error() {
+        let message = serde_json::from_str::<api::ErrorResponse>(&body)
+            .ok()
+            .and_then(|response| response.message)
+            .unwrap_or_else(|| status.canonical_reason().unwrap_or_default().into());
+        return Err(RequestError::api(status, message));
+    }
 
-            let api_url = std::format!("{V1_API_URL}/messages/inbox");
-
-            tracing::debug!(url = api_url, cursor = ?next_cursor, "Requesting inbox...");
-
-            let response = self
-                .http_client
-                .post(api_url)
-                .header("x-api-key", self.api_key.expose_secret())
-                .json(&request)
-                .send()
-                .await?
-                .error_for_status()?;
-
-            let status = response.status();
-            let body = response.text().await?;
-
-            match serde_json::from_str::<api::FetchResponse>(&body) {
-                Ok(api::FetchResponse::Success { data }) => {
-                    let inbox_data: api::InboxData =
-                        serde_json::from_value(data).map_err(|err| {
-                            tracing::error!(?err, "failed to parse inbox data");
-                            RequestError::InvalidJson(err)
-                        })?;
-
-                    for conv in inbox_data.conversations {
-                        let Some(conv_id) = conv["conversation_id"].as_str() else {
-                            continue;
-                        };
-                        if conv_id.contains(id) {
-                            return Ok(Some(conv_id.into()));
-                        }
-                    }
+    Ok((status, body))
+}
 
-                    // Check if there are more pages
-                    if let Some(cursor) = inbox_data.next_cursor {
-                        next_cursor = Some(cursor);
-                    } else {
-                        return Ok(None);
-                    }
-                }
-                Ok(api::FetchResponse::Error { message }) => {
-                    return Err(RequestError::Api(message).into());
-                }
-                Err(err) => {
-                    tracing::error!(?err, ?status, ?body, "failed to parse response");
-                    return Err(RequestError::ParseError { status, body }.into());
-                }
+/// Parses a timestamp in RFC 3339 format, or in seconds or milliseconds
+/// since the Unix epoch.
+pub fn parse_timestamp(value: &serde_json::Value) -> Option<Timestamp> {
+    match value {
+        serde_json::Value::Number(n) => {
+            let n = n.as_i64()?;
+            // Distinguish epoch milliseconds from epoch seconds:
+            if n > 100_000_000_000 {
+                Timestamp::from_millisecond(n).ok()
+            } else {
+                Timestamp::from_second(n).ok()
             }
         }
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Returns when a conversation was last active, or a message was sent, if
+/// known.
+fn activity_time(value: &serde_json::Value) -> Option<Timestamp> {
+    let last_message = value.get("last_message");
+    [
+        "last_activity_at",
+        "updated_at",
+        "timestamp",
+        "sent_at",
+        "created_at",
+    ]
+    .iter()
+    .find_map(|key| {
+        let value = value
+            .get(key)
+            .or_else(|| last_message.and_then(|message| message.get(key)))?;
+        parse_timestamp(value)
+    })
+}
+
+/// A LinkedIn resource that [`Client::fetch`] supports.
+enum Resource {
+    Profile(url::Url),
+    Company(url::Url),
+    Listing(Listing),
+}
+
+/// A LinkedIn listing, fetched page by page.
+enum Listing {
+    /// A page-numbered listing of the items under `field`.
+    Pages {
+        endpoint: &'static str,
+        field: &'static str,
+    },
+    Inbox,
+    /// The messages of the conversation at the URL.
+    Conversation(url::Url),
+}
+
+impl Listing {
+    const CONNECTIONS: Self = Self::Pages {
+        endpoint: "/network/connections",
+        field: "connections",
+    };
+
+    /// Returns a guard for fetching the listing, identifying its items by
+    /// the fields that are unique to them.
+    fn pagination(&self, max_pages: u32) -> Pagination {
+        let id_keys: &'static [&'static str] = match self {
+            Self::Pages {
+                field: "invitations",
+                ..
+            } => &["invitation_id", "entity_urn", "urn", "id"],
+            Self::Pages { .. } => &[
+                "public_identifier",
+                "profile_url",
+                "linkedin_url",
+                "entity_urn",
+                "urn",
+                "id",
+            ],
+            Self::Inbox => &["conversation_id"],
+            Self::Conversation(_) => &["message_id", "entity_urn", "urn", "id"],
+        };
+        Pagination::with_id_keys(max_pages, id_keys)
+    }
+}
+
+impl Resource {
+    fn parse(url: &str) -> Result<Self, FetchError> {
+        let url = parse_linkedin_url(url)
+            .map_err(FetchError::InvalidUrl)?
+            .ok_or_else(|| FetchError::UnknownResource(url.into()))?;
+
+        let path = url.path();
+        Ok(if path.starts_with("/in/") {
+            Self::Profile(url)
+        } else if path.starts_with("/company/") {
+            Self::Company(url)
+        } else if path.starts_with("/messaging/thread/") {
+            Self::Listing(Listing::Conversation(url))
+        } else if path.starts_with("/messaging") {
+            Self::Listing(Listing::Inbox)
+        } else if path.starts_with("/mynetwork/invite-connect/connections") {
+            Self::Listing(Listing::CONNECTIONS)
+        } else if path.starts_with("/mynetwork/invitation-manager/sent") {
+            Self::Listing(Listing::Pages {
+                endpoint: "/network/sent-invitations",
+                field: "invitations",
+            })
+        } else if path.starts_with("/mynetwork/invitation-manager") {
+            Self::Listing(Listing::Pages {
+                endpoint: "/network/invitations",
+                field: "invitations",
+            })
+        } else {
+            return Err(FetchError::UnknownResource(url.into()));
+        })
+    }
+}
+
+/// Parses `url`, returning `None` when it doesn't point to LinkedIn.
+/// Returns the conversation of `conversations` whose id is the thread `id`
+/// of a `/messaging/thread/:id` URL.
+fn matching_conversation(
+    conversations: Vec<serde_json::Value>,
+    id: &str,
+) -> Option<serde_json::Value> {
+    conversations.into_iter().find(|conv| {
+        conv["conversation_id"]
+            .as_str()
+            .is_some_and(|conv_id| is_thread(conv_id, id))
+    })
+}
+
+/// Returns whether `conversation_id` is the thread `id`, either exactly or
+/// as the last segment of a URN such as `urn:li:msg_conversation:(…,id)`.
+/// Partial ids match no conversation, rather than an unrelated one.
+fn is_thread(conversation_id: &str, id: &str) -> bool {
+    !id.is_empty()
+        && (conversation_id == id
+            || conversation_id
+                .rsplit([':', ',', '(', ')'])
+                .find(|segment| !segment.is_empty())
+                == Some(id))
+}
+
+fn parse_linkedin_url(url: &str) -> Result<Option<url::Url>, url::ParseError> {
+    let url = url::Url::try_from(url)?;
+
+    if url
+        .host_str()
+        .is_none_or(|host| !host.ends_with("linkedin.com"))
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threads_match_conversation_ids_exactly() {
+        assert!(is_thread("2-abc", "2-abc"));
+        assert!(is_thread(
+            "urn:li:msg_conversation:(urn:li:fsd_profile:X,2-abc)",
+            "2-abc"
+        ));
+        assert!(is_thread("urn:li:fs_conversation:2-abc", "2-abc"));
+        assert!(!is_thread("2-abcdef", "2-abc"));
+        assert!(!is_thread("urn:li:fs_conversation:2-abc", "abc"));
+        assert!(!is_thread(
+            "urn:li:fs_conversation:2-abc",
+            "fs_conversation"
+        ));
+        assert!(!is_thread("2-abc", ""));
+    }
+
+    #[test]
+    fn partial_thread_ids_match_no_other_conversation() {
+        let conversations = std::vec![
+            json!({"conversation_id": "urn:li:fs_conversation:2-abcdef"}),
+            json!({"conversation_id": "urn:li:fs_conversation:x2-abc"}),
+            json!({"conversation_id": "urn:li:fs_conversation:2-abc"}),
+        ];
+        assert_eq!(
+            matching_conversation(conversations.clone(), "2-abc"),
+            Some(conversations[2].clone())
+        );
+        assert_eq!(matching_conversation(conversations, "2-ab"), None);
     }
 }
@@ -1,5 +1,6 @@
 // This is free and unencumbered software released into the public domain.
 
+use crate::LoginResult;
 use std::string::String;
 use std::vec::Vec;
 
@@ -17,28 +18,84 @@ pub enum LoginResponse {
     Error { message: String },
 }
 
+// Either contains the login_token, requiring no further auth flow:
+// {
+//   "status": "success",
+//   "login_token": "<token>",
+//   "message": "Login successful"
+// }
+// or describes a challenge to complete first:
+// {
+//   "status": "success",
+//   "message": "Check your email for verification code",
+//   "email": "<email>"
+// }
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-#[serde(untagged)]
-pub enum LoginResponseType {
-    // Already contains login_token and hence requires no further auth flow.
-    // {
-    //   "status": "success",
-    //   "login_token": "<token>",
-    //   "message": "Login successful"
-    // }
-    WithToken {
-        login_token: String,
-        message: String,
-    },
-    // {
-    //   "status": "success",
-    //   "message": "Check your email for verification code",
-    //   "email": "<email>"
-    // }
-    NeedCode {
-        email: String,
-        message: String,
-    },
+pub struct LoginResponseType {
+    pub login_token: Option<String>,
+    #[serde(default)]
+    pub message: String,
+    #[serde(flatten)]
+    pub rest: serde_json::Map<String, serde_json::Value>,
+}
+
+impl From<LoginResponseType> for LoginResult {
+    fn from(response: LoginResponseType) -> Self {
+        if let Some(login_token) = response.login_token {
+            return LoginResult::GotToken { login_token };
+        }
+
+        // The challenge is identified by the whole words of the message and
+        // of any field naming it, so that e.g. "pin" doesn't match "shipping":
+        let mut description = response.message.to_lowercase();
+        for field in ["type", "challenge", "challenge_type", "method"] {
+            if let Some(value) = response.rest.get(field).and_then(|value| value.as_str()) {
+                description.push(' ');
+                description.push_str(&value.to_lowercase());
+            }
+        }
+        let words = words(&description);
+        let mentions = |phrases: &[&str]| {
+            phrases.iter().any(|phrase| {
+                let phrase = self::words(phrase);
+                words.windows(phrase.len()).any(|window| window == phrase)
+            })
+        };
+
+        let message = response.message;
+        if mentions(&["captcha", "recaptcha"]) {
+            LoginResult::NeedCaptcha { message }
+        } else if mentions(&["authenticator", "two-factor", "2fa", "totp"]) {
+            LoginResult::NeedTwoFactorCode { message }
+        } else if mentions(&[
+            "approve",
+            "approved",
+            "approval",
+            "in-app",
+            "linkedin app",
+            "mobile app",
+            "pending",
+        ]) {
+            LoginResult::Pending { message }
+        } else if mentions(&["code", "verification", "verify", "pin"])
+            || response.rest.contains_key("email")
+        {
+            LoginResult::NeedCode { message }
+        } else {
+            LoginResult::Challenge {
+                message,
+                response: serde_json::Value::Object(response.rest),
+            }
+        }
+    }
+}
+
+/// Splits `text` into its words, separated by anything but letters and
+/// digits, so that e.g. "two-factor" and "two_factor" are the same words.
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect()
 }
 
 // {
@@ -62,16 +119,83 @@ pub enum VerifyResponse {
 #[serde(tag = "status")]
 pub enum FetchResponse {
     // {"status":"success","data": { ... }}
+    // {"status":"success","message":"Message sent"}
     #[serde(rename = "success")]
-    Success { data: serde_json::Value },
+    Success {
+        #[serde(default)]
+        data: serde_json::Value,
+    },
     // {"status":"error","message":"Invalid parameter"}
     #[serde(rename = "error")]
     Error { message: String },
 }
 
+// The body of a response with an error status, if JSON:
+// {"status":"error","message":"Insufficient credits"}
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ErrorResponse {
+    #[serde(alias = "error", alias = "detail")]
+    pub message: Option<String>,
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct InboxData {
     pub conversations: Vec<serde_json::Value>,
     pub total_results: u32,
     pub next_cursor: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login_result(response: serde_json::Value) -> LoginResult {
+        serde_json::from_value::<LoginResponseType>(response)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn pin_matches_only_the_whole_word() {
+        let result = login_result(serde_json::json!({"message": "Enter the PIN we sent you"}));
+        assert!(matches!(result, LoginResult::NeedCode { .. }));
+
+        for message in [
+            "Spinning up your session",
+            "Shipping soon",
+            "Account pinned",
+        ] {
+            let result = login_result(serde_json::json!({ "message": message }));
+            assert!(
+                matches!(result, LoginResult::Challenge { .. }),
+                "{message}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn challenge_type_field_is_matched() {
+        let result = login_result(serde_json::json!({"message": "", "challenge_type": "PIN"}));
+        assert!(matches!(result, LoginResult::NeedCode { .. }));
+
+        let result = login_result(serde_json::json!({"message": "", "type": "two_factor"}));
+        assert!(matches!(result, LoginResult::NeedTwoFactorCode { .. }));
+
+        let result = login_result(serde_json::json!({"message": "", "method": "in-app"}));
+        assert!(matches!(result, LoginResult::Pending { .. }));
+    }
+
+    #[test]
+    fn phrases_match_consecutive_words() {
+        let result = login_result(serde_json::json!({
+            "message": "Open the LinkedIn app to approve the sign-in"
+        }));
+        assert!(matches!(result, LoginResult::Pending { .. }));
+
+        let result = login_result(serde_json::json!({"message": "Please solve the CAPTCHA"}));
+        assert!(matches!(result, LoginResult::NeedCaptcha { .. }));
+
+        let result = login_result(serde_json::json!({"message": "Login token issued"}));
+        assert!(matches!(result, LoginResult::Challenge { .. }));
+    }
+}
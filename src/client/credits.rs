@@ -0,0 +1,256 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::{
+    INBOX_ENDPOINT, Listing, PageSizes, Resource, ResumePoint,
+    error::{FetchError, RequestError},
+};
+use core::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// The credits a Linkup API endpoint bills for a call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CreditCost {
+    Free,
+    PerCall(u32),
+    /// Billed for each page of the requested `start_page`..=`end_page` range.
+    PerPage(u32),
+}
+
+/// The cost of each Linkup API endpoint the client calls. Endpoints not
+/// listed are assumed to cost [`DEFAULT_CREDIT_COST`].
+pub const CREDIT_COSTS: &[(&str, CreditCost)] = &[
+    ("/auth/login", CreditCost::Free),
+    ("/auth/verify", CreditCost::Free),
+    ("/profile/info", CreditCost::PerCall(1)),
+    ("/companies/info", CreditCost::PerCall(1)),
+    ("/messages/inbox", CreditCost::PerCall(1)),
+    ("/messages/conversation", CreditCost::PerPage(1)),
+    ("/messages/send-message", CreditCost::PerCall(1)),
+    ("/network/connections", CreditCost::PerPage(1)),
+    ("/network/invitations", CreditCost::PerPage(1)),
+    ("/network/sent-invitations", CreditCost::PerPage(1)),
+    ("/network/connect", CreditCost::PerCall(1)),
+    ("/network/accept-invitations", CreditCost::PerCall(1)),
+    ("/network/decline-invitations", CreditCost::PerCall(1)),
+    ("/network/withdraw-invitation", CreditCost::PerCall(1)),
+    ("/posts/create", CreditCost::PerCall(1)),
+    ("/posts/comment", CreditCost::PerCall(1)),
+    ("/posts/react", CreditCost::PerCall(1)),
+];
+
+pub const DEFAULT_CREDIT_COST: CreditCost = CreditCost::PerCall(1);
+
+impl CreditCost {
+    /// Returns the cost of `endpoint` from [`CREDIT_COSTS`].
+    pub fn of(endpoint: &str) -> Self {
+        CREDIT_COSTS
+            .iter()
+            .find(|(known, _)| *known == endpoint)
+            .map_or(DEFAULT_CREDIT_COST, |(_, cost)| *cost)
+    }
+
+    /// Returns the credits billed for a call requesting `pages` pages.
+    pub fn credits(self, pages: u32) -> u32 {
+        match self {
+            Self::Free => 0,
+            Self::PerCall(credits) => credits,
+            Self::PerPage(credits) => credits.saturating_mul(pages.max(1)),
+        }
+    }
+
+    /// Returns the credits billed for posting `request` to `endpoint`.
+    pub fn of_request(endpoint: &str, request: &serde_json::Value) -> u32 {
+        let pages = match (request["start_page"].as_u64(), request["end_page"].as_u64()) {
+            (Some(start), Some(end)) if end >= start => (end - start + 1) as u32,
+            _ => 1,
+        };
+        Self::of(endpoint).credits(pages)
+    }
+}
+
+/// A limit on the credits a [`Client`](super::Client) may spend, shared by
+/// its clones. A request that would exceed it fails with
+/// [`RequestError::BudgetExceeded`] before it is sent.
+#[derive(Clone, Debug)]
+pub struct CreditBudget {
+    limit: u32,
+    spent: Arc<AtomicU32>,
+}
+
+impl CreditBudget {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            spent: Arc::default(),
+        }
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// The credits spent so far, counting failed requests too.
+    pub fn spent(&self) -> u32 {
+        self.spent.load(Ordering::Relaxed)
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.limit.saturating_sub(self.spent())
+    }
+
+    /// Spends `cost` credits, unless that would exceed the limit.
+    pub fn charge(&self, cost: u32) -> Result<(), RequestError> {
+        self.spent
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |spent| {
+                spent.checked_add(cost).filter(|&total| total <= self.limit)
+            })
+            .map(|_| ())
+            .map_err(|spent| RequestError::BudgetExceeded {
+                limit: self.limit,
+                spent,
+                cost,
+            })
+    }
+}
+
+/// The API calls and credits fetching a resource takes, as estimated by
+/// [`Estimate::of`] without making any requests.
+///
+/// The length of a listing isn't known before fetching it, so its minimum is
+/// that of a single page, and its maximum only the bound set by the maximum
+/// number of pages, as the listing is `capped`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Estimate {
+    pub min_calls: u32,
+    pub max_calls: u32,
+    pub min_credits: u32,
+    pub max_credits: u32,
+    /// Whether the maxima are just the bound of the maximum number of pages.
+    pub capped: bool,
+}
+
+impl Estimate {
+    /// Estimates fetching the resource at `url`, from `resume` on if given,
+    /// with the current `page_sizes` and up to `max_pages` pages per listing.
+    ///
+    /// The maxima include backing off once through the smaller page sizes,
+    /// as when the API times out.
+    pub fn of(
+        url: &str,
+        resume: Option<&ResumePoint>,
+        page_sizes: &PageSizes,
+        max_pages: u32,
+    ) -> Result<Self, FetchError> {
+        let listing = match Resource::parse(url)? {
+            Resource::Profile(_) => return Ok(Self::call("/profile/info")),
+            Resource::Company(_) => return Ok(Self::call("/companies/info")),
+            Resource::Listing(listing) => listing,
+        };
+
+        let pages = |endpoint: &'static str, size: u32| {
+            let cost = CreditCost::of(endpoint);
+            let backoff = page_sizes.backoff(endpoint);
+            let retries = backoff.iter().map(|&size| cost.credits(size)).sum::<u32>();
+            let credits = cost.credits(size);
+            Self {
+                min_calls: 1,
+                max_calls: max_pages.saturating_add(backoff.len() as u32),
+                min_credits: credits,
+                max_credits: credits.saturating_mul(max_pages).saturating_add(retries),
+                capped: true,
+            }
+        };
+        let inbox = || pages(INBOX_ENDPOINT, 1);
+        let conversation = "/messages/conversation";
+
+        Ok(match listing {
+            Listing::Pages { endpoint, .. } => pages(endpoint, page_sizes.current(endpoint)),
+            Listing::Inbox => inbox(),
+            // A conversation is looked up in the inbox, unless resumed:
+            Listing::Conversation(_) => match resume {
+                Some(ResumePoint::Conversation { .. }) => {
+                    pages(conversation, page_sizes.current(conversation))
+                }
+                _ => inbox() + pages(conversation, page_sizes.current(conversation)),
+            },
+        })
+    }
+
+    fn call(endpoint: &str) -> Self {
+        let credits = CreditCost::of(endpoint).credits(1);
+        Self {
+            min_calls: 1,
+            max_calls: 1,
+            min_credits: credits,
+            max_credits: credits,
+            capped: false,
+        }
+    }
+}
+
+impl core::ops::Add for Estimate {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            min_calls: self.min_calls.saturating_add(other.min_calls),
+            max_calls: self.max_calls.saturating_add(other.max_calls),
+            min_credits: self.min_credits.saturating_add(other.min_credits),
+            max_credits: self.max_credits.saturating_add(other.max_credits),
+            capped: self.capped || other.capped,
+        }
+    }
+}
+
+impl core::ops::AddAssign for Estimate {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    const CONNECTIONS: &str = "https://linkedin.com/mynetwork/invite-connect/connections";
+    const THREAD: &str = "https://linkedin.com/messaging/thread/2-abc";
+
+    #[test]
+    fn estimate_bounds_listings_by_max_pages_and_backoff() {
+        let estimate = Estimate::of(CONNECTIONS, None, &PageSizes::default(), 50).unwrap();
+        // Pages of 10 API pages, backing off to 5, 2 and 1:
+        assert_eq!(
+            estimate,
+            Estimate {
+                min_calls: 1,
+                max_calls: 53,
+                min_credits: 10,
+                max_credits: 508,
+                capped: true,
+            }
+        );
+    }
+
+    #[test]
+    fn estimate_of_a_profile_is_exact() {
+        let url = "https://linkedin.com/in/someone";
+        let estimate = Estimate::of(url, None, &PageSizes::default(), 50).unwrap();
+        assert_eq!((estimate.min_credits, estimate.max_credits), (1, 1));
+        assert!(!estimate.capped);
+    }
+
+    #[test]
+    fn estimate_of_a_resumed_conversation_skips_the_inbox() {
+        let page_sizes = PageSizes::default();
+        let fresh = Estimate::of(THREAD, None, &page_sizes, 5).unwrap();
+        let resume = ResumePoint::Conversation {
+            conversation_id: "2-abc".to_string(),
+            page: 3,
+        };
+        let resumed = Estimate::of(THREAD, Some(&resume), &page_sizes, 5).unwrap();
+        assert!(resumed.max_calls < fresh.max_calls);
+        assert_eq!(fresh.min_calls, 2);
+        assert_eq!(resumed.min_calls, 1);
+    }
+}
@@ -1,6 +1,9 @@
 // This is free and unencumbered software released into the public domain.
 
+use crate::ResumePoint;
+use std::boxed::Box;
 use std::string::String;
+use std::vec::Vec;
 
 #[derive(Debug, thiserror::Error)]
 pub enum RequestError {
@@ -8,19 +11,206 @@ pub enum RequestError {
     Http(#[from] reqwest::Error),
     #[error("failed to parse response: {0}")]
     InvalidJson(#[from] serde_json::Error),
-    #[error("API response is an error: {0}")]
-    Api(String),
+    #[error("API response is an error ({kind}, status {status}): {message}")]
+    Api {
+        kind: ApiErrorKind,
+        status: reqwest::StatusCode,
+        message: String,
+    },
     #[error("failed to parse response as expected type, got status {status}: {body}")]
     ParseError {
         status: reqwest::StatusCode,
         body: String,
     },
+    #[error("failed to log in: {0}")]
+    Login(#[source] Box<LoginError>),
+    #[error("credit budget exceeded: {spent} of {limit} credits spent, the request costs {cost}")]
+    BudgetExceeded { limit: u32, spent: u32, cost: u32 },
+}
+
+impl RequestError {
+    /// Returns the error reported by the Linkup API, classifying its
+    /// `message` and HTTP `status`.
+    pub fn api(status: reqwest::StatusCode, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self::Api {
+            kind: ApiErrorKind::classify(status, &message),
+            status,
+            message,
+        }
+    }
+
+    /// Returns the kind of error reported by the Linkup API, if any.
+    pub fn api_error_kind(&self) -> Option<ApiErrorKind> {
+        match self {
+            Self::Api { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the request was rejected because the login token
+    /// expired or was revoked.
+    pub fn is_session_expired(&self) -> bool {
+        self.api_error_kind() == Some(ApiErrorKind::InvalidToken)
+    }
+
+    /// Returns whether the request may succeed if retried later, after a
+    /// network failure, rate limiting or a server error.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Http(err) => err.is_timeout() || err.is_connect(),
+            Self::Api { kind, .. } => kind.is_retryable(),
+            Self::Login(err) => err.request_error().is_some_and(Self::is_retryable),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the request wasn't sent as it would have spent more
+    /// credits than budgeted.
+    pub fn is_budget_exceeded(&self) -> bool {
+        matches!(self, Self::BudgetExceeded { .. })
+    }
+
+    /// Returns whether the request was rejected for its API key or login
+    /// token, or logging in failed for reasons other than the network.
+    pub fn is_auth(&self) -> bool {
+        match self {
+            Self::Api { kind, .. } => kind.is_auth(),
+            Self::Login(err) => err.is_auth(),
+            _ => false,
+        }
+    }
+}
+
+/// The kind of error reported by the Linkup API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ApiErrorKind {
+    /// The login token expired, was revoked or is malformed.
+    InvalidToken,
+    /// The Linkup API key is missing or invalid.
+    InvalidApiKey,
+    /// The Linkup account has run out of credits.
+    InsufficientCredits,
+    /// Too many requests were made in too short a time.
+    RateLimited,
+    /// The requested profile, company, conversation or post doesn't exist.
+    NotFound,
+    /// The request has a missing or invalid parameter.
+    InvalidParameter,
+    /// The Linkup API or LinkedIn failed to handle the request.
+    Server,
+    Other,
+}
+
+impl ApiErrorKind {
+    /// Classifies an error by its message, falling back to its HTTP status.
+    pub fn classify(status: reqwest::StatusCode, message: &str) -> Self {
+        use reqwest::StatusCode;
+
+        let message = message.to_lowercase();
+        let mentions = |words: &[&str]| words.iter().any(|word| message.contains(word));
+
+        if mentions(&["api key", "api_key", "apikey", "x-api-key"]) {
+            Self::InvalidApiKey
+        } else if mentions(&["token", "session", "cookie"])
+            && mentions(&["expired", "invalid", "revoked", "unauthorized", "not valid"])
+        {
+            Self::InvalidToken
+        } else if mentions(&["credit", "insufficient balance", "payment required"]) {
+            Self::InsufficientCredits
+        } else if mentions(&["rate limit", "too many requests", "slow down"]) {
+            Self::RateLimited
+        } else if mentions(&["not found", "does not exist", "doesn't exist", "no such"]) {
+            Self::NotFound
+        } else {
+            match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::InvalidToken,
+                StatusCode::PAYMENT_REQUIRED => Self::InsufficientCredits,
+                StatusCode::NOT_FOUND | StatusCode::GONE => Self::NotFound,
+                StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+                StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                    Self::InvalidParameter
+                }
+                status if status.is_server_error() => Self::Server,
+                _ if mentions(&["invalid", "missing", "required", "parameter"]) => {
+                    Self::InvalidParameter
+                }
+                _ => Self::Other,
+            }
+        }
+    }
+
+    /// Returns whether a request failing with this error may succeed if
+    /// retried later.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::RateLimited | Self::Server)
+    }
+
+    /// Returns whether the API key or login token was rejected.
+    pub fn is_auth(self) -> bool {
+        matches!(self, Self::InvalidToken | Self::InvalidApiKey)
+    }
+}
+
+impl core::fmt::Display for ApiErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::InvalidToken => "invalid login token",
+            Self::InvalidApiKey => "invalid API key",
+            Self::InsufficientCredits => "insufficient credits",
+            Self::RateLimited => "rate limited",
+            Self::NotFound => "not found",
+            Self::InvalidParameter => "invalid parameter",
+            Self::Server => "server error",
+            Self::Other => "error",
+        })
+    }
+}
+
+impl From<LoginError> for RequestError {
+    fn from(value: LoginError) -> Self {
+        Self::Login(Box::new(value))
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum LoginError {
     #[error(transparent)]
     Request(#[from] RequestError),
+    #[error("no login token or credentials to log in with")]
+    NoCredentials,
+    #[error("verification code required: {0}")]
+    CodeRequired(String),
+    #[error("sign-in was not approved in the LinkedIn app in time: {0}")]
+    ApprovalTimeout(String),
+    #[error("CAPTCHA required: {0}")]
+    CaptchaRequired(String),
+    #[error("unsupported login challenge: {0}")]
+    UnsupportedChallenge(String),
+    #[error("code verification failed: {0}")]
+    Verify(#[from] VerifyError),
+}
+
+impl LoginError {
+    /// Returns the error of the login or verification request, if any.
+    pub fn request_error(&self) -> Option<&RequestError> {
+        match self {
+            Self::Request(err) | Self::Verify(VerifyError::Request(err)) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns whether logging in failed for the credentials, as when they
+    /// or the verification code were rejected, or a code or challenge is
+    /// required, rather than for a network failure or server error.
+    pub fn is_auth(&self) -> bool {
+        match self.request_error() {
+            Some(RequestError::Api { kind, .. }) => !kind.is_retryable(),
+            Some(_) => false,
+            None => true,
+        }
+    }
 }
 
 impl From<reqwest::Error> for LoginError {
@@ -47,8 +237,29 @@ pub enum FetchError {
     InvalidUrl(#[from] url::ParseError),
     #[error("unknown resource: {0}")]
     UnknownResource(String),
+    #[error("not found: {0}")]
+    NotFound(String),
     #[error(transparent)]
     Request(#[from] RequestError),
+    /// A paginated fetch failed after fetching some pages, which can be
+    /// resumed with [`Client::fetch_from`](crate::Client::fetch_from).
+    #[error("{source} (after fetching {} items)", items.len())]
+    Partial {
+        items: Vec<serde_json::Value>,
+        resume: ResumePoint,
+        #[source]
+        source: RequestError,
+    },
+}
+
+impl FetchError {
+    /// Returns the error of a request, looking through partial results.
+    pub fn request_error(&self) -> Option<&RequestError> {
+        match self {
+            Self::Request(e) | Self::Partial { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl From<reqwest::Error> for FetchError {
@@ -56,3 +267,166 @@ impl From<reqwest::Error> for FetchError {
         FetchError::Request(RequestError::Http(value))
     }
 }
+
+impl From<LoginError> for FetchError {
+    fn from(value: LoginError) -> Self {
+        FetchError::Request(value.into())
+    }
+}
+
+#[cfg(feature = "write")]
+#[derive(Debug, thiserror::Error)]
+pub enum WriteError {
+    #[error("invalid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("unknown resource: {0}")]
+    UnknownResource(String),
+    #[error(transparent)]
+    Request(#[from] RequestError),
+}
+
+#[cfg(feature = "write")]
+impl From<reqwest::Error> for WriteError {
+    fn from(value: reqwest::Error) -> Self {
+        WriteError::Request(RequestError::Http(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    fn classify(status: u16, message: &str) -> ApiErrorKind {
+        ApiErrorKind::classify(StatusCode::from_u16(status).unwrap(), message)
+    }
+
+    #[test]
+    fn messages_are_classified() {
+        use ApiErrorKind::*;
+        for (message, kind) in [
+            ("Invalid API key", InvalidApiKey),
+            ("Missing x-api-key header", InvalidApiKey),
+            ("Login token expired", InvalidToken),
+            ("Session is not valid anymore", InvalidToken),
+            ("Cookie revoked", InvalidToken),
+            ("Insufficient credits", InsufficientCredits),
+            ("Payment required", InsufficientCredits),
+            ("Rate limit exceeded, slow down", RateLimited),
+            ("Too many requests", RateLimited),
+            ("Profile not found", NotFound),
+            ("This conversation doesn't exist", NotFound),
+        ] {
+            assert_eq!(classify(200, message), kind, "{message}");
+        }
+    }
+
+    #[test]
+    fn messages_take_precedence_over_statuses() {
+        assert_eq!(classify(400, "Profile not found"), ApiErrorKind::NotFound);
+        assert_eq!(
+            classify(500, "Rate limit exceeded"),
+            ApiErrorKind::RateLimited
+        );
+        assert_eq!(
+            classify(403, "Not enough credits"),
+            ApiErrorKind::InsufficientCredits
+        );
+    }
+
+    #[test]
+    fn earlier_rules_win() {
+        // A rejected token parameter is an invalid token, not an invalid parameter:
+        assert_eq!(
+            classify(400, "invalid parameter: login_token"),
+            ApiErrorKind::InvalidToken
+        );
+        // An invalid API key is reported as such, even alongside a token:
+        assert_eq!(
+            classify(401, "Invalid API key or login token"),
+            ApiErrorKind::InvalidApiKey
+        );
+        // Tokens are only invalid when the message says so:
+        assert_eq!(
+            classify(500, "Failed to start session"),
+            ApiErrorKind::Server
+        );
+        assert_eq!(classify(200, "Token not found"), ApiErrorKind::NotFound);
+    }
+
+    #[test]
+    fn statuses_are_classified_without_a_known_message() {
+        use ApiErrorKind::*;
+        for (status, kind) in [
+            (401, InvalidToken),
+            (403, InvalidToken),
+            (402, InsufficientCredits),
+            (404, NotFound),
+            (410, NotFound),
+            (429, RateLimited),
+            (400, InvalidParameter),
+            (422, InvalidParameter),
+            (500, Server),
+            (502, Server),
+            (200, Other),
+        ] {
+            assert_eq!(classify(status, "Something went wrong"), kind, "{status}");
+        }
+        assert_eq!(classify(200, "Missing field: url"), InvalidParameter);
+        assert_eq!(classify(500, "Missing field: url"), Server);
+    }
+
+    #[test]
+    fn kinds_are_retryable_or_auth() {
+        use ApiErrorKind::*;
+        assert!(RateLimited.is_retryable() && Server.is_retryable());
+        assert!(!NotFound.is_retryable() && !InvalidToken.is_retryable());
+        assert!(InvalidToken.is_auth() && InvalidApiKey.is_auth());
+        assert!(!InsufficientCredits.is_auth());
+    }
+
+    fn login_error(error: LoginError) -> RequestError {
+        RequestError::from(error)
+    }
+
+    #[test]
+    fn rejected_logins_are_auth_failures() {
+        for error in [
+            LoginError::NoCredentials,
+            LoginError::CodeRequired("Enter the code".into()),
+            LoginError::CaptchaRequired("Solve the CAPTCHA".into()),
+            LoginError::Request(RequestError::api(
+                StatusCode::BAD_REQUEST,
+                "Invalid email or password",
+            )),
+            LoginError::Verify(VerifyError::Request(RequestError::api(
+                StatusCode::BAD_REQUEST,
+                "Wrong verification code",
+            ))),
+        ] {
+            let error = login_error(error);
+            assert!(error.is_auth(), "{error}");
+            assert!(!error.is_retryable(), "{error}");
+        }
+    }
+
+    #[test]
+    fn failed_logins_may_be_retried() {
+        for error in [
+            LoginError::Request(RequestError::api(StatusCode::BAD_GATEWAY, "Bad gateway")),
+            LoginError::Request(RequestError::api(StatusCode::OK, "Too many requests")),
+            LoginError::Request(RequestError::ParseError {
+                status: StatusCode::OK,
+                body: "<html>".into(),
+            }),
+        ] {
+            let error = login_error(error);
+            assert!(!error.is_auth(), "{error}");
+        }
+        let error = login_error(LoginError::Request(RequestError::api(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Service unavailable",
+        )));
+        assert!(error.is_retryable());
+    }
+}
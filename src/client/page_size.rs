@@ -0,0 +1,212 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::error::RequestError;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::vec::Vec;
+
+/// The Linkup API endpoint listing inbox conversations, sized in
+/// conversations per request.
+pub const INBOX_ENDPOINT: &str = "/messages/inbox";
+
+/// The range of sizes to request pages of an endpoint in: conversations
+/// per request for the inbox, and pages per request for the page-numbered
+/// endpoints, such as connections and conversation messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageSize {
+    /// The size to start with.
+    pub max: u32,
+    /// The smallest size to back off to.
+    pub min: u32,
+}
+
+impl PageSize {
+    pub const fn new(max: u32, min: u32) -> Self {
+        Self { max, min }
+    }
+
+    /// A size that never changes.
+    pub const fn fixed(size: u32) -> Self {
+        Self::new(size, size)
+    }
+}
+
+/// The page sizes a [`Client`](super::Client) requests from each paginated
+/// endpoint.
+///
+/// Each endpoint starts at its maximum size. When the API times out or
+/// returns empty `data` at a size, the client retries at half the size, down
+/// to the minimum, and keeps using the size that worked for the rest of the
+/// session. Clones share the sizes learned.
+#[derive(Clone, Debug)]
+pub struct PageSizes {
+    sizes: BTreeMap<&'static str, PageSize>,
+    learned: Arc<Mutex<BTreeMap<&'static str, u32>>>,
+}
+
+impl Default for PageSizes {
+    fn default() -> Self {
+        Self {
+            sizes: BTreeMap::from([(INBOX_ENDPOINT, PageSize::new(100, 10))]),
+            learned: Arc::default(),
+        }
+    }
+}
+
+impl PageSizes {
+    /// The size range of the page-numbered endpoints not otherwise set.
+    pub const DEFAULT_PAGES: PageSize = PageSize::new(10, 1);
+
+    /// Sets the size range of `endpoint`, e.g. `/network/connections`.
+    pub fn with(mut self, endpoint: &'static str, size: PageSize) -> Self {
+        self.sizes.insert(endpoint, size);
+        self.learned.lock().unwrap().remove(endpoint);
+        self
+    }
+
+    /// Returns the size range of `endpoint`.
+    pub fn get(&self, endpoint: &str) -> PageSize {
+        self.sizes
+            .get(endpoint)
+            .copied()
+            .unwrap_or(Self::DEFAULT_PAGES)
+    }
+
+    /// Returns the size to request from `endpoint` now.
+    pub fn current(&self, endpoint: &str) -> u32 {
+        let size = self.get(endpoint);
+        match self.learned.lock().unwrap().get(endpoint) {
+            Some(&learned) => learned.clamp(size.min, size.max),
+            None => size.max,
+        }
+    }
+
+    /// Returns the size to back off to from `size`, if any.
+    pub(super) fn smaller(&self, endpoint: &str, size: u32) -> Option<u32> {
+        let min = self.get(endpoint).min;
+        (size > min).then(|| (size / 2).max(min))
+    }
+
+    /// Returns the sizes `endpoint` may back off to from its current size.
+    pub(super) fn backoff(&self, endpoint: &str) -> Vec<u32> {
+        let mut sizes = Vec::new();
+        let mut size = self.current(endpoint);
+        while let Some(smaller) = self.smaller(endpoint, size) {
+            sizes.push(smaller);
+            size = smaller;
+        }
+        sizes
+    }
+
+    /// Requests a page of `endpoint` with `request` at the current size,
+    /// backing off to smaller sizes while it times out or returns empty
+    /// `data`, and returns the data along with the size requested.
+    ///
+    /// A page that `continues` a listing, whose previous page returned data
+    /// at the current size, is only retried on timeouts, as empty data is
+    /// then just the end of the listing. A smaller size is only remembered
+    /// once it returns data, as empty data may also be the end of a listing.
+    pub(super) async fn request<F, Fut>(
+        &self,
+        endpoint: &'static str,
+        continues: bool,
+        mut request: F,
+    ) -> Result<(serde_json::Value, u32), RequestError>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<serde_json::Value, RequestError>>,
+    {
+        let initial_size = self.current(endpoint);
+        let mut size = initial_size;
+        loop {
+            let result = request(size).await;
+            let empty =
+                matches!(&result, Ok(data) if data.as_array().is_some_and(|data| data.is_empty()));
+            let failed = match &result {
+                Ok(_) => empty && !continues,
+                Err(RequestError::Http(e)) => e.is_timeout(),
+                Err(_) => false,
+            };
+            match self.smaller(endpoint, size) {
+                Some(smaller) if failed => {
+                    tracing::warn!(endpoint, size, "retrying with {smaller} per request");
+                    size = smaller;
+                }
+                _ => {
+                    if !empty && size != initial_size && result.is_ok() {
+                        self.learn(endpoint, size);
+                    }
+                    return result.map(|data| (data, size));
+                }
+            }
+        }
+    }
+
+    /// Remembers that `size` works for `endpoint`.
+    pub(super) fn learn(&self, endpoint: &'static str, size: u32) {
+        let mut learned = self.learned.lock().unwrap();
+        if learned.get(endpoint) != Some(&size) {
+            tracing::info!(endpoint, size, "using the smaller page size from now on");
+            learned.insert(endpoint, size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+    use serde_json::json;
+
+    const CONNECTIONS: &str = "/network/connections";
+
+    /// Requests a page answered by `respond`, returning the sizes requested.
+    async fn requested_sizes(
+        page_sizes: &PageSizes,
+        continues: bool,
+        respond: impl Fn(u32) -> serde_json::Value,
+    ) -> Vec<u32> {
+        let sizes = RefCell::new(Vec::new());
+        page_sizes
+            .request(CONNECTIONS, continues, |size| {
+                sizes.borrow_mut().push(size);
+                core::future::ready(Ok(respond(size)))
+            })
+            .await
+            .unwrap();
+        sizes.into_inner()
+    }
+
+    #[tokio::test]
+    async fn end_of_listing_takes_one_request() {
+        let page_sizes = PageSizes::default();
+        let sizes = requested_sizes(&page_sizes, true, |_| json!([])).await;
+        assert_eq!(sizes, [10]);
+        assert_eq!(page_sizes.current(CONNECTIONS), 10);
+    }
+
+    #[tokio::test]
+    async fn empty_first_page_backs_off() {
+        let page_sizes = PageSizes::default();
+        let sizes = requested_sizes(&page_sizes, false, |_| json!([])).await;
+        assert_eq!(sizes, [10, 5, 2, 1]);
+        // Empty data at every size is just an empty listing:
+        assert_eq!(page_sizes.current(CONNECTIONS), 10);
+    }
+
+    #[tokio::test]
+    async fn size_returning_data_is_remembered() {
+        let page_sizes = PageSizes::default();
+        let respond = |size| match size {
+            10 => json!([]),
+            _ => json!({"connections": [{"id": 1}]}),
+        };
+        let sizes = requested_sizes(&page_sizes, false, respond).await;
+        assert_eq!(sizes, [10, 5]);
+        assert_eq!(page_sizes.current(CONNECTIONS), 5);
+
+        // The next page starts at the remembered size:
+        let sizes = requested_sizes(&page_sizes, true, |_| json!([])).await;
+        assert_eq!(sizes, [5]);
+    }
+}
@@ -0,0 +1,226 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::types::{Page, ResumePoint};
+use core::hash::{Hash, Hasher};
+use std::collections::BTreeSet;
+use std::string::{String, ToString};
+
+/// The most pages of a listing a [`Client`](super::Client) fetches by
+/// default, each of up to 10 API pages.
+pub const DEFAULT_MAX_PAGES: u32 = 1000;
+
+/// The fields identifying the items of any listing, most specific first.
+const ID_KEYS: &[&str] = &[
+    "message_id",
+    "invitation_id",
+    "conversation_id",
+    "entity_urn",
+    "urn",
+    "id",
+    "public_identifier",
+    "profile_url",
+    "linkedin_url",
+];
+
+/// Guards the paginated fetch of a listing against an API that repeats
+/// cursors or pages, or never runs out of them, and drops the items already
+/// fetched from later pages.
+///
+/// Create one per listing with [`Client::pagination`](super::Client::pagination)
+/// and pass each page fetched through [`check`](Self::check).
+#[derive(Clone, Debug)]
+pub struct Pagination {
+    max_pages: u32,
+    id_keys: &'static [&'static str],
+    pages: u32,
+    resume_points: BTreeSet<ResumePoint>,
+    page_digests: BTreeSet<u64>,
+    item_ids: BTreeSet<String>,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PAGES)
+    }
+}
+
+impl Pagination {
+    /// Allows up to `max_pages` pages, identifying items by common id fields.
+    pub fn new(max_pages: u32) -> Self {
+        Self::with_id_keys(max_pages, ID_KEYS)
+    }
+
+    pub(super) fn with_id_keys(max_pages: u32, id_keys: &'static [&'static str]) -> Self {
+        Self {
+            max_pages,
+            id_keys,
+            pages: 0,
+            resume_points: BTreeSet::new(),
+            page_digests: BTreeSet::new(),
+            item_ids: BTreeSet::new(),
+        }
+    }
+
+    /// The number of pages checked so far.
+    pub fn pages(&self) -> u32 {
+        self.pages
+    }
+
+    /// Returns `page` without the items fetched before, and as the last page
+    /// if it repeats an earlier page, points back to one, or reaches the
+    /// maximum number of pages.
+    pub fn check(&mut self, mut page: Page) -> Page {
+        self.pages += 1;
+
+        if !page.items.is_empty() && !self.page_digests.insert(digest(&page.items)) {
+            tracing::warn!(
+                page = self.pages,
+                "pagination loop: got the same page twice, stopping"
+            );
+            return Page::default();
+        }
+
+        let count = page.items.len();
+        page.items.retain(|item| match item_id(item, self.id_keys) {
+            Some(id) => self.item_ids.insert(id),
+            None => true,
+        });
+        if page.items.len() < count {
+            tracing::warn!(
+                page = self.pages,
+                "dropped {} items already fetched",
+                count - page.items.len()
+            );
+        }
+
+        match page.next.take() {
+            Some(next) if !self.resume_points.insert(next.clone()) => {
+                tracing::warn!(?next, "pagination loop: got a repeated cursor, stopping");
+            }
+            Some(_) if self.pages >= self.max_pages => {
+                tracing::warn!("stopping after the maximum of {} pages", self.max_pages);
+            }
+            next => page.next = next,
+        }
+
+        page
+    }
+}
+
+/// Returns the value of the first of `keys` that `item` has.
+fn item_id(item: &serde_json::Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| match &item[key] {
+        serde_json::Value::String(id) => Some(std::format!("{key}:{id}")),
+        serde_json::Value::Number(id) => Some(std::format!("{key}:{id}")),
+        _ => None,
+    })
+}
+
+fn digest(items: &[serde_json::Value]) -> u64 {
+    let mut hasher = std::hash::DefaultHasher::new();
+    for item in items {
+        item.to_string().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::vec::Vec;
+
+    fn page(ids: &[u32], next: Option<u32>) -> Page {
+        Page {
+            items: ids.iter().map(|id| json!({ "id": id })).collect(),
+            next: next.map(ResumePoint::Page),
+        }
+    }
+
+    fn ids(page: &Page) -> Vec<u64> {
+        page.items
+            .iter()
+            .map(|item| item["id"].as_u64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn pages_pass_through_until_the_last() {
+        let mut pagination = Pagination::new(10);
+        let first = pagination.check(page(&[1, 2], Some(2)));
+        assert_eq!(ids(&first), [1, 2]);
+        assert_eq!(first.next, Some(ResumePoint::Page(2)));
+
+        let last = pagination.check(page(&[3], None));
+        assert_eq!(ids(&last), [3]);
+        assert_eq!(last.next, None);
+        assert_eq!(pagination.pages(), 2);
+    }
+
+    #[test]
+    fn repeated_cursor_stops() {
+        let mut pagination = Pagination::new(10);
+        pagination.check(page(&[1], Some(2)));
+        let repeated = pagination.check(page(&[2], Some(2)));
+        assert_eq!(ids(&repeated), [2]);
+        assert_eq!(repeated.next, None);
+    }
+
+    #[test]
+    fn identical_page_stops() {
+        let mut pagination = Pagination::new(10);
+        pagination.check(page(&[1, 2], Some(2)));
+        let identical = pagination.check(page(&[1, 2], Some(3)));
+        assert!(identical.items.is_empty());
+        assert_eq!(identical.next, None);
+    }
+
+    #[test]
+    fn empty_pages_are_not_identical() {
+        let mut pagination = Pagination::new(10);
+        pagination.check(page(&[], Some(2)));
+        let next = pagination.check(page(&[], Some(3)));
+        assert_eq!(next.next, Some(ResumePoint::Page(3)));
+    }
+
+    #[test]
+    fn items_fetched_before_are_dropped() {
+        let mut pagination = Pagination::new(10);
+        pagination.check(page(&[1, 2], Some(2)));
+        let overlapping = pagination.check(page(&[2, 3], Some(3)));
+        assert_eq!(ids(&overlapping), [3]);
+        assert_eq!(overlapping.next, Some(ResumePoint::Page(3)));
+    }
+
+    #[test]
+    fn items_are_identified_by_the_most_specific_key() {
+        let mut pagination = Pagination::new(10);
+        let first = Page {
+            items: std::vec![
+                json!({"message_id": "a", "id": 1}),
+                json!({"message_id": "b", "id": 1}),
+                json!({"text": "no id"}),
+            ],
+            next: Some(ResumePoint::Page(2)),
+        };
+        assert_eq!(pagination.check(first).items.len(), 3);
+
+        let second = Page {
+            items: std::vec![
+                json!({"message_id": "a", "id": 2}),
+                json!({"text": "no id"}),
+            ],
+            next: None,
+        };
+        assert_eq!(pagination.check(second).items, [json!({"text": "no id"})]);
+    }
+
+    #[test]
+    fn max_pages_stops() {
+        let mut pagination = Pagination::new(2);
+        assert!(pagination.check(page(&[1], Some(2))).next.is_some());
+        let last = pagination.check(page(&[2], Some(3)));
+        assert_eq!(ids(&last), [2]);
+        assert_eq!(last.next, None);
+    }
+}
@@ -0,0 +1,43 @@
+// This is free and unencumbered software released into the public domain.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{sync::Mutex, time::Instant};
+
+/// Spaces out API requests made through a [`Client`](super::Client).
+///
+/// Clones share the same schedule, so a single limiter can be used by
+/// several clients.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimiter {
+    /// Allows one request per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Arc::default(),
+        }
+    }
+
+    /// Allows `count` requests per minute.
+    pub fn per_minute(count: u32) -> Self {
+        Self::new(Duration::from_secs(60) / count.max(1))
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Waits until the next request is allowed.
+    pub async fn wait(&self) {
+        let mut next = self.next.lock().await;
+        if let Some(at) = *next {
+            tokio::time::sleep_until(at).await;
+        }
+        *next = Some(Instant::now() + self.interval);
+    }
+}
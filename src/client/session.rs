@@ -0,0 +1,297 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::{COUNTRY, Client, error::*};
+use crate::{LoginResult, TokenRecord};
+use asimov_module::secrecy::{ExposeSecret, SecretString};
+use jiff::Timestamp;
+use std::string::String;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The LinkedIn credentials a [`Client`] logs in with.
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    pub email: SecretString,
+    pub password: SecretString,
+}
+
+impl Credentials {
+    pub fn new(email: impl Into<SecretString>, password: impl Into<SecretString>) -> Self {
+        Self {
+            email: email.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Returns the account that login tokens are stored under, the
+    /// normalized email address.
+    pub fn account(&self) -> String {
+        self.email.expose_secret().trim().to_lowercase()
+    }
+}
+
+/// Provides the verification code when LinkedIn requires one to log in.
+///
+/// The provider is called from within the client's requests and may block,
+/// e.g. to prompt on a terminal, as both methods run on a thread for blocking
+/// work.
+pub trait VerificationCodeProvider: Send + Sync {
+    /// Returns the verification code requested by the Linkup API's
+    /// `message`, or `None` if no code is available.
+    fn verification_code(&self, message: &str) -> Option<SecretString>;
+
+    /// Called once when the sign-in awaits approval in the LinkedIn app, e.g.
+    /// to tell the user. The client checks a few times until it is approved.
+    fn approval_pending(&self, message: &str) {
+        let _ = message;
+    }
+}
+
+impl<F> VerificationCodeProvider for F
+where
+    F: Fn(&str) -> Option<SecretString> + Send + Sync,
+{
+    fn verification_code(&self, message: &str) -> Option<SecretString> {
+        self(message)
+    }
+}
+
+/// How long to wait before first checking whether a sign-in was approved in
+/// the LinkedIn app. The Linkup API has no endpoint for the status of a
+/// sign-in, so each check submits the credentials again.
+const APPROVAL_FIRST_DELAY: Duration = Duration::from_secs(15);
+
+/// The longest wait between checks, as the wait doubles after each.
+const APPROVAL_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How many times to check before giving up, about 5 minutes in all.
+const APPROVAL_MAX_CHECKS: u32 = 6;
+
+/// A login token to start a [`Client`] with, and when it was issued, if
+/// known.
+#[derive(Clone, Debug)]
+pub struct LoginToken {
+    pub token: SecretString,
+    pub issued_at: Option<Timestamp>,
+}
+
+impl From<SecretString> for LoginToken {
+    fn from(token: SecretString) -> Self {
+        Self {
+            token,
+            issued_at: None,
+        }
+    }
+}
+
+impl From<String> for LoginToken {
+    fn from(token: String) -> Self {
+        SecretString::from(token).into()
+    }
+}
+
+impl From<&str> for LoginToken {
+    fn from(token: &str) -> Self {
+        SecretString::from(token).into()
+    }
+}
+
+impl From<TokenRecord> for LoginToken {
+    fn from(record: TokenRecord) -> Self {
+        Self {
+            token: record.token,
+            issued_at: record.issued_at,
+        }
+    }
+}
+
+/// The login token shared by clones of a client.
+#[derive(Debug, Default)]
+pub(super) struct Session {
+    token: RwLock<Option<LoginToken>>,
+    /// Held while logging in, so that concurrent requests log in only once.
+    login: tokio::sync::Mutex<()>,
+}
+
+impl Session {
+    pub(super) fn new(token: LoginToken) -> Arc<Self> {
+        Arc::new(Self {
+            token: RwLock::new(Some(token)),
+            ..Default::default()
+        })
+    }
+
+    pub(super) fn token(&self) -> Option<SecretString> {
+        Some(self.token.read().unwrap().as_ref()?.token.clone())
+    }
+
+    fn issued_at(&self) -> Option<Timestamp> {
+        self.token.read().unwrap().as_ref()?.issued_at
+    }
+
+    fn set_token(&self, token: LoginToken) {
+        *self.token.write().unwrap() = Some(token);
+    }
+}
+
+impl Client {
+    /// Returns the current login token, if any.
+    pub fn login_token(&self) -> Option<SecretString> {
+        self.session.token()
+    }
+
+    /// Returns when the current login token was issued, if known.
+    pub fn login_token_issued_at(&self) -> Option<Timestamp> {
+        self.session.issued_at()
+    }
+
+    /// Logs in with the client's credentials, replacing its login token and
+    /// saving the new one to its token store.
+    #[tracing::instrument(skip_all)]
+    pub async fn login(&self) -> Result<SecretString, LoginError> {
+        let _login = self.session.login.lock().await;
+        self.login_locked().await
+    }
+
+    /// Returns a login token to use instead of `expired`, logging in again
+    /// unless a concurrent request already did.
+    pub(super) async fn refresh(
+        &self,
+        expired: Option<&SecretString>,
+    ) -> Result<SecretString, LoginError> {
+        let _login = self.session.login.lock().await;
+        match (self.session.token(), expired) {
+            (Some(token), None) => Ok(token),
+            (Some(token), Some(expired)) if token.expose_secret() != expired.expose_secret() => {
+                Ok(token)
+            }
+            _ => self.login_locked().await,
+        }
+    }
+
+    /// Logs in again before a long series of requests if the login token is
+    /// older than the client's maximum token age, or its age is unknown.
+    pub(super) async fn refresh_if_stale(&self) -> Result<(), LoginError> {
+        let (Some(max_age), Some(_)) = (self.max_token_age, &self.credentials) else {
+            return Ok(());
+        };
+        // Without a token, the first request logs in anyway:
+        let Some(token) = self.session.token() else {
+            return Ok(());
+        };
+
+        let age = self
+            .session
+            .issued_at()
+            .map(|issued_at| Timestamp::now().duration_since(issued_at));
+        if age.is_some_and(|age| age.unsigned_abs() <= max_age) {
+            return Ok(());
+        }
+
+        tracing::info!(?age, ?max_age, "login token too old, logging in again");
+        self.refresh(Some(&token)).await?;
+        Ok(())
+    }
+
+    /// Records in the token store that the Linkup API accepted `token`.
+    pub(super) fn mark_validated(&self, token: &SecretString) {
+        let (Some(credentials), Some(token_store)) = (&self.credentials, &self.token_store) else {
+            return;
+        };
+
+        let saved = match token_store.load(&credentials.account()) {
+            Ok(Some(mut record)) if record.token.expose_secret() == token.expose_secret() => {
+                record.last_validated_at = Some(Timestamp::now());
+                token_store.save(&record)
+            }
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = saved {
+            tracing::warn!("failed to update stored login token: {e}");
+        }
+    }
+
+    async fn login_locked(&self) -> Result<SecretString, LoginError> {
+        let Some(credentials) = &self.credentials else {
+            return Err(LoginError::NoCredentials);
+        };
+
+        let mut checks = 0;
+        let mut delay = APPROVAL_FIRST_DELAY;
+
+        let token: SecretString = loop {
+            let result = super::login(
+                &self.http_client,
+                &self.api_key,
+                &credentials.email,
+                &credentials.password,
+            )
+            .await?;
+
+            match result {
+                LoginResult::GotToken { login_token } => break login_token.into(),
+                LoginResult::NeedCode { message } | LoginResult::NeedTwoFactorCode { message } => {
+                    let code = match self.verification_code_provider.clone() {
+                        Some(provider) => {
+                            let message = message.clone();
+                            tokio::task::spawn_blocking(move || {
+                                provider.verification_code(&message)
+                            })
+                            .await
+                            .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+                        }
+                        None => None,
+                    }
+                    .ok_or(LoginError::CodeRequired(message))?;
+                    break super::verify(
+                        &self.http_client,
+                        &self.api_key,
+                        &credentials.email,
+                        &code,
+                    )
+                    .await?
+                    .into();
+                }
+                LoginResult::Pending { message } => {
+                    if checks >= APPROVAL_MAX_CHECKS {
+                        return Err(LoginError::ApprovalTimeout(message));
+                    }
+                    if let (0, Some(provider)) = (checks, self.verification_code_provider.clone()) {
+                        let message = message.clone();
+                        tokio::task::spawn_blocking(move || provider.approval_pending(&message))
+                            .await
+                            .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+                    }
+                    checks += 1;
+                    tracing::debug!(?delay, "waiting for the sign-in to be approved: {message}");
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(APPROVAL_MAX_DELAY);
+                }
+                LoginResult::NeedCaptcha { message } => {
+                    return Err(LoginError::CaptchaRequired(message));
+                }
+                LoginResult::Challenge { message, .. } => {
+                    return Err(LoginError::UnsupportedChallenge(message));
+                }
+            }
+        };
+
+        let mut record = TokenRecord::new(credentials.account(), token.clone(), COUNTRY);
+        record.last_validated_at = record.issued_at;
+        self.session.set_token(LoginToken {
+            token: token.clone(),
+            issued_at: record.issued_at,
+        });
+
+        let saved = match &self.token_store {
+            Some(token_store) => token_store.save(&record),
+            None => Ok(()),
+        };
+        if let Err(e) = saved {
+            tracing::warn!("failed to save login token: {e}");
+        }
+
+        Ok(token)
+    }
+}
@@ -1,9 +1,95 @@
 // This is free and unencumbered software released into the public domain.
 
 use std::string::String;
+use std::vec::Vec;
 
+/// Where to resume a paginated fetch that failed midway, as reported by
+/// [`FetchError::Partial`](crate::error::FetchError::Partial).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResumePoint {
+    /// The first page not yet fetched of a page-numbered listing, such as
+    /// connections and invitations.
+    Page(u32),
+    /// The cursor of the next inbox page not yet fetched.
+    Cursor(String),
+    /// The first page of messages not yet fetched of a conversation.
+    Conversation { conversation_id: String, page: u32 },
+}
+
+/// A page of a listing, as returned by [`Client::fetch_page`](crate::Client::fetch_page).
+#[derive(Clone, Debug, Default)]
+pub struct Page {
+    pub items: Vec<serde_json::Value>,
+    /// Where the next page starts, or `None` for the last page.
+    pub next: Option<ResumePoint>,
+}
+
+/// The outcome of a login attempt: either a login token, or a challenge
+/// LinkedIn requires to be completed first.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum LoginResult {
-    GotToken { login_token: String },
-    NeedCode { message: String },
+    GotToken {
+        login_token: String,
+    },
+    /// A verification code was sent by email or SMS; submit it with
+    /// [`verify`](crate::verify).
+    NeedCode {
+        message: String,
+    },
+    /// A code from an authenticator app is required; submit it with
+    /// [`verify`](crate::verify).
+    NeedTwoFactorCode {
+        message: String,
+    },
+    /// The sign-in awaits approval in the LinkedIn app; log in again to poll
+    /// until it is approved.
+    Pending {
+        message: String,
+    },
+    /// A CAPTCHA must be solved, which is only possible in a browser.
+    NeedCaptcha {
+        message: String,
+    },
+    /// A challenge not otherwise recognized, with the full API response.
+    Challenge {
+        message: String,
+        response: serde_json::Value,
+    },
+}
+
+/// A reaction to a LinkedIn post.
+#[cfg(feature = "write")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Reaction {
+    Like,
+    #[serde(rename = "PRAISE")]
+    Celebrate,
+    #[serde(rename = "APPRECIATION")]
+    Support,
+    #[serde(rename = "EMPATHY")]
+    Love,
+    #[serde(rename = "INTEREST")]
+    Insightful,
+    #[serde(rename = "ENTERTAINMENT")]
+    Funny,
+}
+
+#[cfg(feature = "write")]
+impl core::str::FromStr for Reaction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "like" => Ok(Self::Like),
+            "celebrate" | "praise" => Ok(Self::Celebrate),
+            "support" | "appreciation" => Ok(Self::Support),
+            "love" | "empathy" => Ok(Self::Love),
+            "insightful" | "interest" => Ok(Self::Insightful),
+            "funny" | "entertainment" => Ok(Self::Funny),
+            _ => Err(std::format!("unknown reaction: {s}")),
+        }
+    }
 }
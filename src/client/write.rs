@@ -0,0 +1,266 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::{Client, error::*, parse_linkedin_url, types::*};
+use serde_json::json;
+use std::string::String;
+
+/// The Linkup API endpoint sending messages.
+pub const SEND_MESSAGE_ENDPOINT: &str = "/messages/send-message";
+
+/// Where [`Client::send_message`] sends a message, parsed from its URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MessageRecipient {
+    /// A LinkedIn profile (`/in/:account`), by its URL.
+    Profile(String),
+    /// An existing conversation (`/messaging/thread/:id`), by its thread id.
+    Conversation(String),
+}
+
+impl MessageRecipient {
+    pub fn parse(url: &str) -> Result<Self, WriteError> {
+        let unknown = || WriteError::UnknownResource(url.into());
+        let parsed = parse_linkedin_url(url)?.ok_or_else(unknown)?;
+
+        let path = parsed.path();
+        if path.starts_with("/in/") {
+            return Ok(Self::Profile(parsed.as_str().into()));
+        }
+        if let Some(id) = path.strip_prefix("/messaging/thread/") {
+            let id = id.trim_end_matches('/');
+            if !id.is_empty() && !id.contains('/') {
+                return Ok(Self::Conversation(id.into()));
+            }
+        }
+        Err(unknown())
+    }
+
+    /// Returns the request to [`SEND_MESSAGE_ENDPOINT`] that sends `text` to
+    /// a profile, or `None` for a conversation, whose `conversation_id`
+    /// [`Client::message_request`] looks up first.
+    pub fn request(&self, text: &str) -> Option<serde_json::Value> {
+        match self {
+            Self::Profile(url) => Some(json!({
+                "linkedin_url": url,
+                "message_text": text,
+            })),
+            Self::Conversation(_) => None,
+        }
+    }
+}
+
+impl Client {
+    /// Sends a message to a LinkedIn profile (`/in/:account`) or to an
+    /// existing conversation (`/messaging/thread/:id`).
+    #[tracing::instrument(skip(self, text), fields(url = url.as_ref()))]
+    pub async fn send_message(
+        &self,
+        url: impl AsRef<str>,
+        text: impl AsRef<str>,
+    ) -> Result<serde_json::Value, WriteError> {
+        let recipient = MessageRecipient::parse(url.as_ref())?;
+        let request = self.message_request(&recipient, text.as_ref()).await?;
+        Ok(self.request(SEND_MESSAGE_ENDPOINT, request).await?)
+    }
+
+    /// Returns the request to [`SEND_MESSAGE_ENDPOINT`] that sends `text` to
+    /// `recipient`, without sending it. Conversations are looked up in the
+    /// inbox for their `conversation_id`.
+    pub async fn message_request(
+        &self,
+        recipient: &MessageRecipient,
+        text: &str,
+    ) -> Result<serde_json::Value, WriteError> {
+        Ok(match recipient {
+            MessageRecipient::Profile(_) => recipient.request(text).unwrap(),
+            MessageRecipient::Conversation(id) => {
+                let unknown =
+                    || WriteError::UnknownResource(std::format!("/messaging/thread/{id}"));
+                let conversation = self
+                    .find_conversation(id)
+                    .await
+                    .map_err(|err| match err {
+                        FetchError::Request(err) => WriteError::Request(err),
+                        _ => unknown(),
+                    })?
+                    .ok_or_else(unknown)?;
+                json!({
+                    "conversation_id": conversation["conversation_id"],
+                    "message_text": text,
+                })
+            }
+        })
+    }
+
+    /// Sends a connection invitation to a LinkedIn profile (`/in/:account`),
+    /// optionally with a personal note.
+    #[tracing::instrument(skip(self, note), fields(url = url.as_ref()))]
+    pub async fn send_invitation(
+        &self,
+        url: impl AsRef<str>,
+        note: Option<&str>,
+    ) -> Result<serde_json::Value, WriteError> {
+        let url = parse_linkedin_url(url.as_ref())?
+            .filter(|url| url.path().starts_with("/in/"))
+            .ok_or_else(|| WriteError::UnknownResource(url.as_ref().into()))?;
+
+        let mut request = json!({
+            "linkedin_url": url.as_str(),
+        });
+
+        if let Some(note) = note {
+            request["message"] = json!(note);
+        }
+
+        Ok(self.request("/network/connect", request).await?)
+    }
+
+    /// Accepts a received connection invitation, as listed by fetching
+    /// `/mynetwork/invitation-manager`.
+    #[tracing::instrument(skip(self, shared_secret))]
+    pub async fn accept_invitation(
+        &self,
+        invitation_id: &str,
+        shared_secret: &str,
+    ) -> Result<serde_json::Value, WriteError> {
+        let request = json!({
+            "invitation_id": invitation_id,
+            "shared_secret": shared_secret,
+        });
+
+        Ok(self.request("/network/accept-invitations", request).await?)
+    }
+
+    /// Declines a received connection invitation, as listed by fetching
+    /// `/mynetwork/invitation-manager`.
+    #[tracing::instrument(skip(self, shared_secret))]
+    pub async fn decline_invitation(
+        &self,
+        invitation_id: &str,
+        shared_secret: &str,
+    ) -> Result<serde_json::Value, WriteError> {
+        let request = json!({
+            "invitation_id": invitation_id,
+            "shared_secret": shared_secret,
+        });
+
+        Ok(self
+            .request("/network/decline-invitations", request)
+            .await?)
+    }
+
+    /// Withdraws a sent connection invitation, as listed by fetching
+    /// `/mynetwork/invitation-manager/sent`.
+    #[tracing::instrument(skip(self))]
+    pub async fn withdraw_invitation(
+        &self,
+        invitation_id: &str,
+    ) -> Result<serde_json::Value, WriteError> {
+        let request = json!({
+            "invitation_id": invitation_id,
+        });
+
+        Ok(self
+            .request("/network/withdraw-invitation", request)
+            .await?)
+    }
+
+    /// Publishes a text post, optionally sharing a link.
+    #[tracing::instrument(skip_all)]
+    pub async fn create_post(
+        &self,
+        text: impl AsRef<str>,
+        link: Option<&str>,
+    ) -> Result<serde_json::Value, WriteError> {
+        let mut request = json!({
+            "message": text.as_ref(),
+        });
+
+        if let Some(link) = link {
+            let link = url::Url::try_from(link)?;
+            request["link"] = json!(link.as_str());
+        }
+
+        Ok(self.request("/posts/create", request).await?)
+    }
+
+    /// Comments on an existing LinkedIn post.
+    #[tracing::instrument(skip(self, text), fields(url = url.as_ref()))]
+    pub async fn comment_post(
+        &self,
+        url: impl AsRef<str>,
+        text: impl AsRef<str>,
+    ) -> Result<serde_json::Value, WriteError> {
+        let url = parse_linkedin_url(url.as_ref())?
+            .ok_or_else(|| WriteError::UnknownResource(url.as_ref().into()))?;
+
+        let request = json!({
+            "post_url": url.as_str(),
+            "message": text.as_ref(),
+        });
+
+        Ok(self.request("/posts/comment", request).await?)
+    }
+
+    /// Reacts to an existing LinkedIn post.
+    #[tracing::instrument(skip(self), fields(url = url.as_ref()))]
+    pub async fn react_post(
+        &self,
+        url: impl AsRef<str>,
+        reaction: Reaction,
+    ) -> Result<serde_json::Value, WriteError> {
+        let url = parse_linkedin_url(url.as_ref())?
+            .ok_or_else(|| WriteError::UnknownResource(url.as_ref().into()))?;
+
+        let request = json!({
+            "post_url": url.as_str(),
+            "reaction_type": reaction,
+        });
+
+        Ok(self.request("/posts/react", request).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_recipients_are_parsed_from_urls() {
+        assert_eq!(
+            MessageRecipient::parse("https://www.linkedin.com/in/someone/").unwrap(),
+            MessageRecipient::Profile("https://www.linkedin.com/in/someone/".into())
+        );
+        assert_eq!(
+            MessageRecipient::parse("https://linkedin.com/messaging/thread/2-abc/").unwrap(),
+            MessageRecipient::Conversation("2-abc".into())
+        );
+    }
+
+    #[test]
+    fn unknown_message_urls_are_rejected() {
+        for url in [
+            "https://example.com/in/someone",
+            "https://linkedin.com/company/acme",
+            "https://linkedin.com/messaging/thread/",
+            "not a url",
+        ] {
+            assert!(MessageRecipient::parse(url).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn only_profile_requests_need_no_lookup() {
+        let profile = MessageRecipient::Profile("https://linkedin.com/in/someone".into());
+        assert_eq!(
+            profile.request("Hi"),
+            Some(json!({
+                "linkedin_url": "https://linkedin.com/in/someone",
+                "message_text": "Hi",
+            }))
+        );
+        assert_eq!(
+            MessageRecipient::Conversation("2-abc".into()).request("Hi"),
+            None
+        );
+    }
+}
@@ -1,16 +1,23 @@
 // This is free and unencumbered software released into the public domain.
 
-use asimov_linkup_module::Client;
-use asimov_module::{
-    ModuleManifest,
-    SysexitsError::{self, *},
-    secrecy::SecretString,
+use asimov_linkup_module::{
+    Client, DEFAULT_MAX_PAGES, Estimate, Page, PageSizes, RateLimiter, ResumePoint, cli,
+    error::{ApiErrorKind, FetchError},
 };
+use asimov_module::SysexitsError::{self, *};
 use clientele::{
     StandardOptions,
     crates::clap::{self, Parser},
 };
-use std::{io::Write, time::Duration};
+use jiff::{Timestamp, civil::Date, tz::TimeZone};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+use tokio::{sync::mpsc, task::JoinSet};
 
 #[cfg(not(feature = "std"))]
 fn main() {
@@ -19,11 +26,17 @@ fn main() {
 
 /// ASIMOV Linkup Fetcher
 #[derive(Debug, Parser)]
-#[command(name = "asimov-linkup-fetcher", long_about)]
+#[command(name = "asimov-linkup-fetcher", long_about, after_long_help = EXIT_STATUS)]
 struct Options {
     #[clap(flatten)]
     flags: StandardOptions,
 
+    #[clap(flatten)]
+    auth: cli::AuthOptions,
+
+    #[clap(flatten)]
+    credits: cli::CreditOptions,
+
     /// The maximum number of resources to list.
     #[arg(value_name = "COUNT", short = 'n', long)]
     limit: Option<usize>,
@@ -32,12 +45,107 @@ struct Options {
     #[arg(value_name = "FORMAT", short = 'o', long)]
     output: Option<String>,
 
+    /// Continue with the remaining URLs when fetching one fails, recording
+    /// each failure as a JSON line on stderr or in the errors file.
+    #[arg(short = 'k', long)]
+    keep_going: bool,
+
+    /// Write a JSON line per failed URL, with its error kind, exit code,
+    /// message and, for listings that failed midway, where to resume, to this
+    /// file. The items fetched before such failures are still output.
+    #[arg(value_name = "FILE", long)]
+    errors_file: Option<PathBuf>,
+
+    /// The number of URLs to fetch at the same time.
+    #[arg(value_name = "COUNT", short = 'j', long, default_value = "1")]
+    concurrency: NonZeroUsize,
+
+    /// Output each URL's resources as soon as they are fetched, rather than
+    /// in the order of the URLs.
+    #[arg(long)]
+    unordered: bool,
+
+    /// The maximum number of API requests per minute [default: unlimited].
+    #[arg(value_name = "COUNT", long)]
+    rate: Option<u32>,
+
+    /// The most pages to fetch of each listing, each of up to 10 API pages,
+    /// in case the API keeps returning more [default: 1000]
+    #[arg(value_name = "COUNT", long, value_parser = clap::value_parser!(u32).range(1..))]
+    max_pages: Option<u32>,
+
+    /// Report the range of API calls and credits the URLs may take, as a JSON
+    /// line per URL, without credentials or fetching them. Exits with an
+    /// error if they may exceed `--max-credits`.
+    #[arg(long)]
+    estimate: bool,
+
+    /// Record the progress of the run in this file, page by page, so that it
+    /// can be resumed with `--resume`.
+    #[arg(value_name = "FILE", long)]
+    checkpoint: Option<PathBuf>,
+
+    /// Continue the run recorded in the checkpoint file, skipping the URLs
+    /// and pages already fetched.
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// Read URLs from a file, or from stdin if `-`, in addition to those
+    /// given as arguments. May be repeated.
+    #[arg(value_name = "FILE", short = 'i', long = "input")]
+    inputs: Vec<PathBuf>,
+
+    /// The format of the input files [default: by file extension, `csv`,
+    /// `jsonl` or else `lines`]
+    #[arg(value_name = "FORMAT", long, value_enum)]
+    input_format: Option<cli::InputFormat>,
+
+    /// The CSV column with the URLs.
+    #[arg(value_name = "NAME", long, default_value = "url")]
+    url_column: String,
+
+    /// The JSON field, or JSON pointer such as `/profile/url`, with the URLs.
+    #[arg(value_name = "NAME", long, default_value = "url")]
+    url_field: String,
+
+    /// Fetch only the inbox conversations and messages newer than this
+    /// timestamp or date, or than the last run with `--since` that fetched
+    /// the same URL, given `last-run`.
+    #[arg(value_name = "WHEN", long, value_parser = parse_since)]
+    since: Option<Since>,
+
+    /// The URLs to fetch. Given `-`, reads URLs from stdin, one per line.
     urls: Vec<String>,
 }
 
+const EXIT_STATUS: &str = "\
+Exit status:
+  0   (EX_OK)           all resources were fetched
+  64  (EX_USAGE)        an unsupported URL, or a parameter rejected by the API
+  65  (EX_DATAERR)      an unparseable API response
+  66  (EX_NOINPUT)      a resource that doesn't exist
+  67  (EX_NOUSER)       a verification code is required to log in
+  69  (EX_UNAVAILABLE)  the API is unreachable, or out of credits or budget
+  75  (EX_TEMPFAIL)     rate limited, a server error or timeout; retry later
+  77  (EX_NOPERM)       the API key, login token or login was rejected
+  78  (EX_CONFIG)       missing or invalid configuration
+
+With --keep-going, the exit status is EX_TEMPFAIL if any failure was
+temporary, else that of the first failure. Authentication failures and
+running out of credits or budget (see --max-credits) still stop right away.
+
+On SIGINT or SIGTERM, the pages fetched so far are written out and recorded in
+the checkpoint file, if any, and the exit status is 130 or 143 respectively.";
+
 #[cfg(feature = "std")]
 #[tokio::main]
-async fn main() -> Result<SysexitsError, SysexitsError> {
+async fn main() -> SysexitsError {
+    // Exit with the error's code rather than the generic failure code:
+    run().await.unwrap_or_else(|code| code)
+}
+
+#[cfg(feature = "std")]
+async fn run() -> Result<SysexitsError, SysexitsError> {
     // Load environment variables from `.env`:
     clientele::dotenv().ok();
 
@@ -63,192 +171,688 @@ async fn main() -> Result<SysexitsError, SysexitsError> {
     #[cfg(feature = "tracing")]
     asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
 
-    if options.urls.is_empty() {
+    // Expand `-` and input files into the URLs they list:
+    let read_urls = |path: &Path| {
+        cli::read_urls(
+            path,
+            options.input_format,
+            &options.url_column,
+            &options.url_field,
+        )
+    };
+    let mut urls = Vec::new();
+    for url in &options.urls {
+        match url.as_str() {
+            "-" => urls.extend(read_urls(Path::new("-"))?),
+            url => urls.push(url.into()),
+        }
+    }
+    for path in &options.inputs {
+        urls.extend(read_urls(path)?);
+    }
+
+    if urls.is_empty() {
         return Ok(EX_OK);
     }
 
-    let manifest = match asimov_module::ModuleManifest::read_manifest("linkup") {
-        Ok(manifest) => manifest,
-        Err(e) => {
-            tracing::error!("failed to read module manifest: {e}");
-            return Err(EX_CONFIG);
-        }
+    // Skip the URLs and pages fetched by the run being resumed:
+    let progress = match (&options.checkpoint, options.resume) {
+        (Some(path), true) => Checkpoint::read(path)?,
+        _ => Checkpoint::default(),
     };
 
-    // Obtain the Linkup API key from the environment:
-    let api_key: SecretString = match manifest.variable("linkup-api-key", None) {
-        Ok(api_key) => api_key.into(),
-        Err(e) => {
-            tracing::error!("failed to get Linkup API key: {e}");
-            return Err(EX_CONFIG); // not configured
-        }
+    if options.estimate {
+        let max_pages = options.max_pages.unwrap_or(DEFAULT_MAX_PAGES);
+        return estimate(&urls, &progress, max_pages, options.credits.max_credits);
+    }
+
+    let manifest = cli::read_manifest()?;
+    let http_client = cli::http_client();
+    let mut client = cli::connect(&http_client, &manifest, &options.auth).await?;
+    client.credit_budget = options.credits.budget();
+    client.rate_limiter = options.rate.map(RateLimiter::per_minute);
+    if let Some(max_pages) = options.max_pages {
+        client.max_pages = max_pages;
+    }
+
+    let errors_file = match &options.errors_file {
+        Some(path) => Some(BufWriter::new(File::create(path).map_err(|e| {
+            tracing::error!("failed to create `{}`: {e}", path.display());
+            EX_CANTCREAT
+        })?)),
+        None => None,
     };
 
-    let http_client = reqwest::Client::builder()
-        .connect_timeout(Duration::from_secs(10))
-        .read_timeout(Duration::from_secs(30))
-        .build()
-        .unwrap();
+    // Fetch only what's new since the given time, or since each URL's
+    // watermark from the last run:
+    let started_at = Timestamp::now();
+    let mut watermarks = match options.since {
+        Some(Since::LastRun) => Watermarks::read()?,
+        _ => Watermarks::default(),
+    };
+    let since = |url: &str| match options.since {
+        Some(Since::At(since)) => Some(since),
+        Some(Since::LastRun) => watermarks.0.get(url).copied(),
+        None => None,
+    };
 
-    // Get or create login token
-    let login_token = match get_saved_token()? {
-        Some(token) => token,
-        None => {
-            let token = login(&http_client, &manifest).await?;
-            save_token(&token)?;
-            token
-        }
+    let count = urls.len();
+    let work: Vec<Work> = urls
+        .into_iter()
+        .filter(|url| !progress.done.contains(url))
+        .map(|url| Work {
+            resume: progress.next.get(&url).cloned(),
+            since: since(&url),
+            url,
+        })
+        .collect();
+    if work.len() < count {
+        tracing::info!("skipping {} URLs already fetched", count - work.len());
+    }
+
+    let checkpoint_file = match &options.checkpoint {
+        Some(path) => Some(Checkpoint::open(path, options.resume)?),
+        None => None,
+    };
+
+    let mut output = Output {
+        stdout: std::io::stdout().lock(),
+        errors_file,
+        checkpoint_file,
+        keep_going: options.keep_going,
+        failures: Vec::new(),
+        completed: Vec::new(),
     };
 
-    let mut client = Client::builder()
-        .login_token(login_token)
-        .api_key(api_key.clone())
-        .http_client(http_client.clone())
-        .build();
+    // Fetch up to `concurrency` URLs at a time, page by page, sharing the
+    // client's login session and rate limiter:
+    let fetch = |work, index, sender| fetch_url(client.clone(), work, index, sender);
+    let concurrency = options.concurrency.get();
+    let interrupted = interrupted();
+    if let Some(status) = fetch_all(
+        &work,
+        concurrency,
+        options.unordered,
+        &mut output,
+        fetch,
+        interrupted,
+    )
+    .await?
+    {
+        tracing::warn!("interrupted, exiting with the pages fetched so far");
+        std::process::exit(status);
+    }
 
+    // Let the next `--since last-run` pick up where this run started, for
+    // the URLs fetched in full:
+    if options.since.is_some() && !output.completed.is_empty() {
+        for url in output.completed.drain(..) {
+            watermarks.0.insert(url, started_at);
+        }
+        watermarks.write()?;
+    }
+
+    output.finish(count)
+}
+
+/// Writes the estimated calls and credits of each URL not yet fetched as a
+/// JSON line, and their total to stderr, without credentials or requests.
+/// Fails if the credits may exceed `max_credits`.
+fn estimate(
+    urls: &[String],
+    progress: &Checkpoint,
+    max_pages: u32,
+    max_credits: Option<u32>,
+) -> Result<SysexitsError, SysexitsError> {
+    let page_sizes = PageSizes::default();
     let mut stdout = std::io::stdout().lock();
-    for url in options.urls {
-        use asimov_linkup_module::error::{FetchError, RequestError};
-        use reqwest::StatusCode;
-
-        let response = match client.fetch(&url).await {
-            Ok(r) => r,
-            Err(FetchError::Request(RequestError::Http(err)))
-                if err.status() == Some(StatusCode::FORBIDDEN) =>
-            {
-                // Token expired, reset and re-login
-                let new_login_token = login(&http_client, &manifest).await?;
-
-                save_token(&new_login_token)?;
-
-                client = Client::builder()
-                    .login_token(new_login_token)
-                    .api_key(api_key.clone())
-                    .http_client(http_client.clone())
-                    .build();
-
-                match client.fetch(&url).await {
-                    Ok(response) => response,
-                    Err(e) => {
-                        tracing::error!("request failed: {e}");
-                        return Err(EX_UNAVAILABLE);
-                    }
-                }
-            }
+    let mut total = Estimate::default();
+    for url in urls.iter().filter(|url| !progress.done.contains(*url)) {
+        let resume = progress.next.get(url);
+        let estimate =
+            Estimate::of(url, resume, &page_sizes, max_pages).map_err(cli::fetch_error)?;
+        let mut record = serde_json::json!(estimate);
+        record["url"] = url.as_str().into();
+        writeln!(&mut stdout, "{record}")?;
+        total += estimate;
+    }
+    stdout.flush()?;
+
+    eprintln!(
+        "Estimated {} to {} calls and {} to {} credits.",
+        total.min_calls, total.max_calls, total.min_credits, total.max_credits,
+    );
+    if total.capped {
+        eprintln!(
+            "The length of listings isn't known before fetching them: the minimum \
+             is one page each, and the maximum only the bound of {max_pages} pages \
+             set by `--max-pages`, plus retries at smaller page sizes."
+        );
+    }
+    match max_credits {
+        Some(max_credits) if total.min_credits > max_credits => {
+            tracing::error!(
+                "at least {} credits are needed, over the budget of {max_credits}",
+                total.min_credits
+            );
+            Err(EX_UNAVAILABLE)
+        }
+        Some(max_credits) if total.max_credits > max_credits => {
+            tracing::error!(
+                "up to {} credits may be needed, over the budget of {max_credits}; \
+                 lower `--max-pages` to stay within it",
+                total.max_credits
+            );
+            Err(EX_UNAVAILABLE)
+        }
+        _ => Ok(EX_OK),
+    }
+}
+
+/// When to fetch resources from, given `--since`.
+#[derive(Clone, Copy, Debug)]
+enum Since {
+    /// The start of the last run with `--since` that fetched the URL.
+    LastRun,
+    At(Timestamp),
+}
+
+fn parse_since(input: &str) -> Result<Since, String> {
+    if input == "last-run" {
+        return Ok(Since::LastRun);
+    }
+    if let Ok(timestamp) = input.parse::<Timestamp>() {
+        return Ok(Since::At(timestamp));
+    }
+    input
+        .parse::<Date>()
+        .and_then(|date| date.to_zoned(TimeZone::UTC))
+        .map(|zoned| Since::At(zoned.timestamp()))
+        .map_err(|_| "expected an RFC 3339 timestamp, a date, or `last-run`".into())
+}
+
+/// The time each URL was last fetched in full with `--since`, kept in the
+/// state directory.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct Watermarks(BTreeMap<String, Timestamp>);
+
+impl Watermarks {
+    fn path() -> PathBuf {
+        cli::state_dir().join("watermarks.json")
+    }
+
+    fn read() -> Result<Self, SysexitsError> {
+        let path = Self::path();
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
             Err(e) => {
-                tracing::error!("request failed: {e}");
-                return Err(EX_UNAVAILABLE);
+                tracing::error!("failed to read `{}`: {e}", path.display());
+                return Err(EX_NOINPUT);
             }
         };
+        serde_json::from_str(&json).map_err(|e| {
+            tracing::error!("failed to parse `{}`: {e}", path.display());
+            EX_DATAERR
+        })
+    }
 
-        match response {
-            serde_json::Value::Array(values) => {
-                for value in values {
-                    serde_json::to_writer(&mut stdout, &value).unwrap();
-                    writeln!(&mut stdout).unwrap();
+    fn write(&self) -> Result<(), SysexitsError> {
+        let path = Self::path();
+        let write = || -> std::io::Result<()> {
+            std::fs::create_dir_all(cli::state_dir())?;
+            std::fs::write(&path, serde_json::to_string_pretty(self)?)
+        };
+        write().map_err(|e| {
+            tracing::error!("failed to write `{}`: {e}", path.display());
+            EX_IOERR
+        })
+    }
+}
+
+/// Fetches the URLs of `work`, up to `concurrency` at a time, with `fetch`,
+/// and outputs their pages as they arrive or in the order of the URLs.
+///
+/// Once `interrupt` completes, cancels the fetches, outputs the pages fetched
+/// so far, even if out of order, and returns the exit status to report.
+async fn fetch_all<W, F, Fut>(
+    work: &[Work],
+    concurrency: usize,
+    unordered: bool,
+    output: &mut Output<W>,
+    fetch: F,
+    interrupt: impl Future<Output = i32>,
+) -> Result<Option<i32>, SysexitsError>
+where
+    W: Write,
+    F: Fn(Work, usize, Sender) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut sender = Some(sender);
+    let mut pending = work.iter().cloned().enumerate();
+    let mut tasks = JoinSet::new();
+    let mut buffered: BTreeMap<usize, Vec<Progress>> = BTreeMap::new();
+    let mut next_index = 0;
+    tokio::pin!(interrupt);
+    loop {
+        while tasks.len() < concurrency {
+            let (Some(tx), Some((index, work))) = (&sender, pending.next()) else {
+                // Let the channel close once the last task finishes:
+                sender = None;
+                break;
+            };
+            tasks.spawn(fetch(work, index, tx.clone()));
+        }
+
+        tokio::select! {
+            message = receiver.recv() => {
+                // The channel closes once every URL was fetched:
+                let Some((index, progress)) = message else {
+                    break;
+                };
+                if unordered {
+                    output.write(&work[index].url, progress)?;
+                    continue;
+                }
+                buffered.entry(index).or_default().push(progress);
+                while let Some(pages) = buffered.get_mut(&next_index) {
+                    let mut finished = false;
+                    for progress in pages.drain(..) {
+                        finished = progress.is_last();
+                        output.write(&work[next_index].url, progress)?;
+                    }
+                    if !finished {
+                        break;
+                    }
+                    buffered.remove(&next_index);
+                    next_index += 1;
                 }
             }
-            value => serde_json::to_writer(&mut stdout, &value).unwrap(),
+            Some(task) = tasks.join_next() => task?,
+            status = &mut interrupt => {
+                tasks.abort_all();
+                while let Ok((index, progress)) = receiver.try_recv() {
+                    buffered.entry(index).or_default().push(progress);
+                }
+                for (index, pages) in buffered {
+                    for progress in pages {
+                        if let Progress::Page(_) = progress {
+                            output.write(&work[index].url, progress)?;
+                        }
+                    }
+                }
+                output.flush()?;
+                return Ok(Some(status));
+            }
         }
     }
 
-    Ok(EX_OK)
+    // Surface any task that panicked after its last page:
+    while let Some(task) = tasks.join_next().await {
+        task?;
+    }
+    Ok(None)
 }
 
-fn get_saved_token() -> Result<Option<String>, SysexitsError> {
-    match keyring::Entry::new("asimov-linkup-module", "login-token")
-        .unwrap()
-        .get_password()
+/// Waits for SIGINT or SIGTERM, returning the exit status to report, 128
+/// plus the signal number as shells do.
+async fn interrupted() -> i32 {
+    #[cfg(unix)]
     {
-        Ok(token) if token.is_empty() => Ok(None),
-        Ok(token) => Ok(Some(token)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => {
-            tracing::error!("failed to read login token from keychain: {e}");
-            Err(EX_UNAVAILABLE)
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut terminate = signal(SignalKind::terminate()).expect("failed to handle SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => 130,
+            _ = terminate.recv() => 143,
         }
     }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        130
+    }
 }
 
-fn save_token(token: &str) -> Result<(), SysexitsError> {
-    keyring::Entry::new("asimov-linkup-module", "login-token")
-        .unwrap()
-        .set_password(token)
-        .map_err(|e| {
-            tracing::error!("failed to save login token to keychain: {e}");
-            EX_UNAVAILABLE
-        })
+/// A URL to fetch, from where to resume it, and since when.
+#[derive(Clone, Debug)]
+struct Work {
+    url: String,
+    resume: Option<ResumePoint>,
+    since: Option<Timestamp>,
 }
 
-async fn login(
-    http_client: &reqwest::Client,
-    manifest: &ModuleManifest,
-) -> Result<String, SysexitsError> {
-    let api_key: SecretString = manifest
-        .variable("linkup-api-key", None)
-        .map(Into::into)
-        .map_err(|e| {
-            tracing::error!("failed to get Linkup API key: {e}");
-            EX_CONFIG
-        })?;
-
-    let email: SecretString = manifest
-        .variable("linkedin-email", None)
-        .map(Into::into)
-        .map_err(|e| {
-            tracing::error!("failed to get LinkedIn email: {e}");
-            EX_CONFIG
-        })?;
-
-    let password: SecretString = manifest
-        .variable("linkedin-password", None)
-        .map(Into::into)
-        .map_err(|e| {
-            tracing::error!("failed to get LinkedIn password: {e}");
-            EX_CONFIG
-        })?;
-
-    let token = match asimov_linkup_module::login(http_client, &api_key, &email, &password).await {
-        Ok(asimov_linkup_module::LoginResult::GotToken { login_token, .. }) => login_token,
-        Ok(asimov_linkup_module::LoginResult::NeedCode { message, .. }) => {
-            let mut stdout = std::io::stdout().lock();
-            let mut stdin = std::io::stdin().lines();
-
-            stdout.write_all(b"Verification code required.\n").unwrap();
-            std::writeln!(&mut stdout, "LinkUp API response: `{message}`").unwrap();
-            stdout.write_all(b"Enter code:\n").unwrap();
-
-            let code = loop {
-                stdout.write_all(b"> ").unwrap();
-                stdout.flush().unwrap();
-
-                match stdin.next() {
-                    Some(Ok(code)) if !code.trim().is_empty() => break code.trim().into(),
-                    Some(Ok(_)) => continue,
-                    Some(Err(e)) => {
-                        tracing::error!("error while reading input: {e}");
-                        return Err(EX_UNAVAILABLE);
-                    }
-                    None => {
-                        tracing::error!("verification code is required");
-                        return Err(EX_UNAVAILABLE);
-                    }
+/// Fetches a URL page by page from its resume point on, sending each page,
+/// or the failure, tagged with the URL's `index`. Pagination loops end the
+/// URL early, and items already sent are left out of later pages.
+async fn fetch_url(client: Client, work: Work, index: usize, sender: Sender) {
+    let Work {
+        url,
+        mut resume,
+        since,
+    } = work;
+    let mut pagination = client.pagination(&url);
+    loop {
+        match client.fetch_page_since(&url, resume.as_ref(), since).await {
+            Ok(page) => {
+                let page = pagination.check(page);
+                let next = page.next.clone();
+                let _ = sender.send((index, Progress::Page(page)));
+                match next {
+                    Some(next) => resume = Some(next),
+                    None => return,
                 }
+            }
+            Err(error) => {
+                let _ = sender.send((index, Progress::Failed(error, resume)));
+                return;
+            }
+        }
+    }
+}
+
+/// Sends the progress of fetching the URL at an index of the work.
+type Sender = mpsc::UnboundedSender<(usize, Progress)>;
+
+/// The progress of fetching a URL.
+enum Progress {
+    Page(Page),
+    /// Fetching failed, at the given page if some were fetched.
+    Failed(FetchError, Option<ResumePoint>),
+}
+
+impl Progress {
+    fn is_last(&self) -> bool {
+        !matches!(self, Self::Page(Page { next: Some(_), .. }))
+    }
+}
+
+/// The URLs and pages fetched by a run, recorded in a checkpoint file as a
+/// JSON line per page.
+#[derive(Debug, Default)]
+struct Checkpoint {
+    done: BTreeSet<String>,
+    next: BTreeMap<String, ResumePoint>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CheckpointEntry {
+    url: String,
+    /// Where the URL's next page starts, or `None` once it's done.
+    next: Option<ResumePoint>,
+}
+
+impl Checkpoint {
+    fn read(path: &Path) -> Result<Self, SysexitsError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                tracing::error!("failed to read checkpoint `{}`: {e}", path.display());
+                return Err(EX_NOINPUT);
+            }
+        };
+
+        let mut checkpoint = Self::default();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            // The last line may be incomplete if the run was killed:
+            let Ok(entry) = serde_json::from_str::<CheckpointEntry>(&line) else {
+                tracing::warn!("ignoring invalid checkpoint entry: {line}");
+                continue;
             };
+            match entry.next {
+                Some(next) => {
+                    checkpoint.next.insert(entry.url, next);
+                }
+                None => {
+                    checkpoint.next.remove(&entry.url);
+                    checkpoint.done.insert(entry.url);
+                }
+            }
+        }
+        Ok(checkpoint)
+    }
 
-            match asimov_linkup_module::verify(http_client, &api_key, &email, &code).await {
-                Ok(token_string) => token_string,
-                Err(e) => {
-                    tracing::error!("code verification failed: {e}");
-                    return Err(EX_UNAVAILABLE);
+    /// Opens the checkpoint file, appending to it when resuming.
+    fn open(path: &Path, resume: bool) -> Result<File, SysexitsError> {
+        let open = || -> std::io::Result<File> {
+            let mut file = File::options()
+                .create(true)
+                .read(true)
+                .append(resume)
+                .write(true)
+                .truncate(!resume)
+                .open(path)?;
+
+            // Terminate an incomplete last line left by a killed run:
+            if file.metadata()?.len() > 0 {
+                let mut last = [0u8];
+                file.seek(SeekFrom::End(-1))?;
+                file.read_exact(&mut last)?;
+                if last[0] != b'\n' {
+                    writeln!(file)?;
+                }
+            }
+            Ok(file)
+        };
+
+        open().map_err(|e| {
+            tracing::error!("failed to open checkpoint `{}`: {e}", path.display());
+            EX_CANTCREAT
+        })
+    }
+}
+
+/// Writes fetched resources to stdout, and records progress and failures.
+struct Output<W> {
+    stdout: W,
+    errors_file: Option<BufWriter<File>>,
+    checkpoint_file: Option<File>,
+    keep_going: bool,
+    failures: Vec<SysexitsError>,
+    /// The URLs whose last page was written.
+    completed: Vec<String>,
+}
+
+impl<W: Write> Output<W> {
+    /// Writes a page fetched from `url` and checkpoints it, or records the
+    /// URL's failure, returning the exit code if the run must stop.
+    fn write(&mut self, url: &str, progress: Progress) -> Result<(), SysexitsError> {
+        let (error, resume) = match progress {
+            Progress::Page(page) => {
+                for item in page.items {
+                    serde_json::to_writer(&mut self.stdout, &item).unwrap();
+                    writeln!(&mut self.stdout).unwrap();
+                }
+                if page.next.is_none() {
+                    self.completed.push(url.into());
+                }
+                if let Some(file) = &mut self.checkpoint_file {
+                    let entry = CheckpointEntry {
+                        url: url.into(),
+                        next: page.next,
+                    };
+                    writeln!(file, "{}", serde_json::to_string(&entry).unwrap())?;
                 }
+                return Ok(());
             }
+            Progress::Failed(error, resume) => (error, resume),
+        };
+
+        let fatal = is_fatal(&error);
+        let kind = cli::fetch_error_kind(&error);
+        let message = error.to_string();
+        let code = cli::fetch_error(error);
+
+        let mut record = serde_json::json!({
+            "url": url,
+            "kind": kind,
+            "code": code.as_i32(),
+            "message": message,
+        });
+        if let Some(resume) = resume {
+            record["resume"] = serde_json::json!(resume);
         }
-        Err(e) => {
-            tracing::error!("login flow failed: {e}");
-            return Err(EX_UNAVAILABLE);
+        match &mut self.errors_file {
+            Some(file) => writeln!(file, "{record}")?,
+            None if self.keep_going => eprintln!("{record}"),
+            None => {}
         }
-    };
 
-    Ok(token)
+        if !self.keep_going || fatal {
+            if let Some(file) = &mut self.errors_file {
+                file.flush()?;
+            }
+            return Err(code);
+        }
+        self.failures.push(code);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), SysexitsError> {
+        self.stdout.flush()?;
+        if let Some(file) = &mut self.errors_file {
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the exit code summarizing the run over `count` URLs.
+    fn finish(mut self, count: usize) -> Result<SysexitsError, SysexitsError> {
+        self.flush()?;
+
+        let Some(&first) = self.failures.first() else {
+            return Ok(EX_OK);
+        };
+        tracing::error!("failed to fetch {} of {count} URLs", self.failures.len());
+        Err(if self.failures.contains(&EX_TEMPFAIL) {
+            EX_TEMPFAIL
+        } else {
+            first
+        })
+    }
+}
+
+/// Returns whether the failure would recur for every remaining URL.
+fn is_fatal(error: &FetchError) -> bool {
+    error.request_error().is_some_and(|e| {
+        e.is_auth()
+            || e.is_budget_exceeded()
+            || e.api_error_kind() == Some(ApiErrorKind::InsufficientCredits)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn work(urls: &[&str]) -> Vec<Work> {
+        urls.iter()
+            .map(|url| Work {
+                url: (*url).into(),
+                resume: None,
+                since: None,
+            })
+            .collect()
+    }
+
+    fn output(keep_going: bool) -> Output<Vec<u8>> {
+        Output {
+            stdout: Vec::new(),
+            errors_file: None,
+            checkpoint_file: None,
+            keep_going,
+            failures: Vec::new(),
+            completed: Vec::new(),
+        }
+    }
+
+    /// Sends two pages of items named after the URL, the first one after a
+    /// delay for the first URL, so that later URLs finish first.
+    async fn fetch_two_pages(work: Work, index: usize, sender: Sender) {
+        if index == 0 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        for page in 1..=2 {
+            let page = Page {
+                items: vec![json!(format!("{}{page}", work.url))],
+                next: (page == 1).then_some(ResumePoint::Page(2)),
+            };
+            let _ = sender.send((index, Progress::Page(page)));
+        }
+    }
+
+    async fn run(
+        work: &[Work],
+        unordered: bool,
+        output: &mut Output<Vec<u8>>,
+        interrupt: impl Future<Output = i32>,
+    ) -> Result<Option<i32>, SysexitsError> {
+        let fetch_all = fetch_all(work, 2, unordered, output, fetch_two_pages, interrupt);
+        tokio::time::timeout(Duration::from_secs(5), fetch_all)
+            .await
+            .expect("the fetch loop didn't finish")
+    }
+
+    #[tokio::test]
+    async fn fetch_all_finishes_in_order() {
+        let work = work(&["a", "b", "c"]);
+        let mut output = output(false);
+        let status = run(&work, false, &mut output, std::future::pending()).await;
+        assert_eq!(status, Ok(None));
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "\"a1\"\n\"a2\"\n\"b1\"\n\"b2\"\n\"c1\"\n\"c2\"\n"
+        );
+        assert_eq!(output.completed, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn fetch_all_finishes_unordered() {
+        let work = work(&["a", "b"]);
+        let mut output = output(false);
+        let status = run(&work, true, &mut output, std::future::pending()).await;
+        assert_eq!(status, Ok(None));
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "\"b1\"\n\"b2\"\n\"a1\"\n\"a2\"\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_all_finishes_after_failures() {
+        let work = work(&["https://example.com/x", "https://example.com/y"]);
+        let mut output = output(true);
+        let fetch = |work: Work, index, sender: Sender| async move {
+            let error = FetchError::UnknownResource(work.url);
+            let _ = sender.send((index, Progress::Failed(error, None)));
+        };
+        let fetch_all = fetch_all(&work, 1, false, &mut output, fetch, std::future::pending());
+        let status = tokio::time::timeout(Duration::from_secs(5), fetch_all)
+            .await
+            .expect("the fetch loop didn't finish");
+        assert_eq!(status, Ok(None));
+        assert_eq!(output.failures, [EX_USAGE, EX_USAGE]);
+    }
+
+    #[tokio::test]
+    async fn fetch_all_returns_the_interrupt_status() {
+        let work = work(&["a", "b"]);
+        let mut output = output(false);
+        let interrupt = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            130
+        };
+        let status = run(&work, false, &mut output, interrupt).await;
+        assert_eq!(status, Ok(Some(130)));
+        // The pages of the second URL are output although the first URL,
+        // still being fetched, comes first:
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "\"b1\"\n\"b2\"\n"
+        );
+    }
 }
@@ -0,0 +1,188 @@
+// This is free and unencumbered software released into the public domain.
+
+use asimov_linkup_module::{Client, cli, error::WriteError};
+use asimov_module::SysexitsError::{self, *};
+use clientele::{
+    StandardOptions,
+    crates::clap::{self, Parser, Subcommand},
+};
+use std::io::Write;
+
+#[cfg(not(feature = "std"))]
+fn main() {
+    unimplemented!("asimov-linkup-inviter requires the 'std' feature")
+}
+
+/// ASIMOV Linkup Inviter
+///
+/// Every performed action is appended to `~/.asimov/state/linkup/audit.jsonl`.
+#[derive(Debug, Parser)]
+#[command(name = "asimov-linkup-inviter", long_about, after_long_help = cli::CONFIRMED_EXIT_STATUS)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    auth: cli::AuthOptions,
+
+    #[clap(flatten)]
+    credits: cli::CreditOptions,
+
+    /// Perform the actions without asking for confirmation, as required
+    /// without a terminal.
+    #[arg(short = 'y', long)]
+    yes: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Send connection invitations to LinkedIn profiles.
+    Send {
+        /// A personal note to include with the invitation.
+        #[arg(value_name = "TEXT", long)]
+        note: Option<String>,
+
+        /// Profile (`/in/:account`) URLs.
+        urls: Vec<String>,
+    },
+
+    /// Accept a received invitation.
+    Accept {
+        /// The `invitation_id` from `/mynetwork/invitation-manager`.
+        invitation_id: String,
+
+        /// The `shared_secret` from `/mynetwork/invitation-manager`.
+        shared_secret: String,
+    },
+
+    /// Decline a received invitation.
+    Decline {
+        /// The `invitation_id` from `/mynetwork/invitation-manager`.
+        invitation_id: String,
+
+        /// The `shared_secret` from `/mynetwork/invitation-manager`.
+        shared_secret: String,
+    },
+
+    /// Withdraw a sent invitation.
+    Withdraw {
+        /// The `invitation_id` from `/mynetwork/invitation-manager/sent`.
+        invitation_id: String,
+    },
+}
+
+impl Command {
+    fn actions(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Command::Send { urls, .. } => urls
+                .iter()
+                .map(|url| ("send-invitation", url.clone()))
+                .collect(),
+            Command::Accept { invitation_id, .. } => {
+                vec![("accept-invitation", invitation_id.clone())]
+            }
+            Command::Decline { invitation_id, .. } => {
+                vec![("decline-invitation", invitation_id.clone())]
+            }
+            Command::Withdraw { invitation_id } => {
+                vec![("withdraw-invitation", invitation_id.clone())]
+            }
+        }
+    }
+
+    async fn perform(
+        &self,
+        client: &Client,
+        target: &str,
+    ) -> Result<serde_json::Value, WriteError> {
+        match self {
+            Command::Send { note, .. } => client.send_invitation(target, note.as_deref()).await,
+            Command::Accept { shared_secret, .. } => {
+                client.accept_invitation(target, shared_secret).await
+            }
+            Command::Decline { shared_secret, .. } => {
+                client.decline_invitation(target, shared_secret).await
+            }
+            Command::Withdraw { .. } => client.withdraw_invitation(target).await,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[tokio::main]
+async fn main() -> SysexitsError {
+    // Exit with the error's code rather than the generic failure code:
+    run().await.unwrap_or_else(|code| code)
+}
+
+#[cfg(feature = "std")]
+async fn run() -> Result<SysexitsError, SysexitsError> {
+    // Load environment variables from `.env`:
+    clientele::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    let Some(command) = options.command else {
+        return Ok(EX_OK);
+    };
+
+    let actions = command.actions();
+    if actions.is_empty() {
+        return Ok(EX_OK);
+    }
+
+    if !options.yes {
+        let mut stderr = std::io::stderr().lock();
+        for (action, target) in &actions {
+            writeln!(&mut stderr, "{action} {target}").unwrap();
+        }
+        drop(stderr);
+
+        cli::confirm("Perform these actions?")?;
+    }
+
+    let manifest = cli::read_manifest()?;
+    let http_client = cli::http_client();
+    let mut client = cli::connect(&http_client, &manifest, &options.auth).await?;
+    client.credit_budget = options.credits.budget();
+
+    let mut stdout = std::io::stdout().lock();
+    for (action, target) in actions {
+        let result = command.perform(&client, &target).await;
+
+        cli::audit(action, &target, &result)?;
+
+        match result {
+            Ok(response) => {
+                serde_json::to_writer(&mut stdout, &response).unwrap();
+                writeln!(&mut stdout).unwrap();
+            }
+            Err(e) => return Err(cli::write_error(e)),
+        }
+    }
+
+    Ok(EX_OK)
+}
@@ -8,3 +8,16 @@ extern crate std;
 
 mod client;
 pub use client::*;
+
+mod template;
+pub use template::*;
+
+mod token_store;
+pub use token_store::*;
+
+#[cfg(feature = "campaign")]
+pub mod campaign;
+
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub mod cli;
@@ -0,0 +1,253 @@
+// This is free and unencumbered software released into the public domain.
+
+use asimov_linkup_module::{RateLimiter, Template, cli, merge_profile_variables};
+use asimov_module::SysexitsError::{self, *};
+use clientele::{
+    StandardOptions,
+    crates::clap::{self, Parser},
+};
+use std::{collections::BTreeSet, io::Write, path::PathBuf};
+
+#[cfg(not(feature = "std"))]
+fn main() {
+    unimplemented!("asimov-linkup-outreach requires the 'std' feature")
+}
+
+/// LinkedIn limits the note of a connection invitation to 300 characters.
+const MAX_NOTE_LENGTH: usize = 300;
+
+/// ASIMOV Linkup Outreach
+///
+/// Sends personalized messages, or connection invitations with notes, to the
+/// profiles listed in a CSV file. The template may use any CSV column as well
+/// as the `first_name`, `last_name`, `full_name`, `company` and `title` of the
+/// fetched profile, e.g. `Hi {{first_name|there}}, how is {{company}}?`.
+///
+/// Recipients that were already messaged, or invited with `--invite`,
+/// according to the audit log at `~/.asimov/state/linkup/audit.jsonl` are
+/// skipped. Recipients must be profile (`/in/:account`) URLs.
+#[derive(Debug, Parser)]
+#[command(name = "asimov-linkup-outreach", long_about, after_long_help = cli::CONFIRMED_EXIT_STATUS)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    auth: cli::AuthOptions,
+
+    #[clap(flatten)]
+    credits: cli::CreditOptions,
+
+    /// The message template file.
+    #[arg(value_name = "FILE", short = 't', long)]
+    template: PathBuf,
+
+    /// Send connection invitations with the rendered text as the note.
+    #[arg(long)]
+    invite: bool,
+
+    /// Write the rendered messages to stdout instead of sending them.
+    #[arg(long)]
+    preview: bool,
+
+    /// The CSV column containing the profile URLs.
+    #[arg(value_name = "NAME", long, default_value = "url")]
+    url_column: String,
+
+    /// The maximum number of API requests per minute.
+    #[arg(value_name = "COUNT", long, default_value_t = 20)]
+    rate: u32,
+
+    /// Send without asking for confirmation, as required without a terminal.
+    #[arg(short = 'y', long)]
+    yes: bool,
+
+    /// The CSV file with one recipient per row.
+    recipients: PathBuf,
+}
+
+#[cfg(feature = "std")]
+#[tokio::main]
+async fn main() -> SysexitsError {
+    // Exit with the error's code rather than the generic failure code:
+    run().await.unwrap_or_else(|code| code)
+}
+
+#[cfg(feature = "std")]
+async fn run() -> Result<SysexitsError, SysexitsError> {
+    // Load environment variables from `.env`:
+    clientele::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    let template = cli::read_text(Some(&options.template))?;
+    let template = Template::parse(&template).map_err(|e| {
+        tracing::error!("invalid template `{}`: {e}", options.template.display());
+        EX_DATAERR
+    })?;
+
+    let recipients = cli::read_recipients(&options.recipients, &options.url_column)?;
+
+    // Reject anything but profiles before spending credits on any:
+    for (url, _) in &recipients {
+        if profile_url(url).is_none() {
+            tracing::error!(url, "not a LinkedIn profile (`/in/:account`) URL");
+            return Err(EX_USAGE);
+        }
+    }
+
+    let action = if options.invite {
+        "send-invitation"
+    } else {
+        "send-message"
+    };
+    let mut contacted = cli::contacted(action)?;
+
+    // Skip recipients contacted before, and those listed more than once, even
+    // with a differently written URL:
+    let mut listed = BTreeSet::new();
+    let recipients: Vec<_> = recipients
+        .into_iter()
+        .filter(|(url, _)| {
+            let target = cli::audit_target(url);
+            if contacted.contains(&target) {
+                tracing::info!(url, "skipping already contacted recipient");
+                return false;
+            }
+            if !listed.insert(target) {
+                tracing::warn!(url, "skipping recipient listed more than once");
+                return false;
+            }
+            true
+        })
+        .collect();
+
+    if recipients.is_empty() {
+        return Ok(EX_OK);
+    }
+
+    if !options.preview && !options.yes {
+        let action = if options.invite { "Invite" } else { "Message" };
+        let prompt = format!("{action} {} recipients?", recipients.len());
+        cli::confirm(&prompt)?;
+    }
+
+    let manifest = cli::read_manifest()?;
+    let http_client = cli::http_client();
+    let mut client = cli::connect(&http_client, &manifest, &options.auth).await?;
+    client.credit_budget = options.credits.budget();
+    client.rate_limiter = Some(RateLimiter::per_minute(options.rate));
+
+    let mut stdout = std::io::stdout().lock();
+    for (url, mut variables) in recipients {
+        if contacted.contains(&cli::audit_target(&url)) {
+            continue;
+        }
+        let Some(profile_url) = profile_url(&url) else {
+            continue;
+        };
+        let profile = client
+            .fetch_profile(&profile_url)
+            .await
+            .map_err(cli::fetch_error)?;
+
+        // Non-empty CSV columns take precedence over the fetched profile:
+        merge_profile_variables(&mut variables, &profile);
+
+        let text = match template.render(&variables) {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::warn!(url, "skipping recipient: {e}");
+                continue;
+            }
+        };
+
+        if options.invite && text.chars().count() > MAX_NOTE_LENGTH {
+            tracing::warn!(
+                url,
+                "skipping recipient: note exceeds {MAX_NOTE_LENGTH} characters"
+            );
+            continue;
+        }
+
+        if options.preview {
+            let preview = serde_json::json!({ "url": url, "text": text });
+            serde_json::to_writer(&mut stdout, &preview).unwrap();
+            writeln!(&mut stdout).unwrap();
+            continue;
+        }
+
+        let result = match options.invite {
+            true => client.send_invitation(&url, Some(&text)).await,
+            false => client.send_message(&url, &text).await,
+        };
+        cli::audit(action, &url, &result)?;
+
+        match result {
+            Ok(response) => {
+                contacted.insert(cli::audit_target(&url));
+                serde_json::to_writer(&mut stdout, &response).unwrap();
+                writeln!(&mut stdout).unwrap();
+            }
+            Err(e) => return Err(cli::write_error(e)),
+        }
+    }
+
+    Ok(EX_OK)
+}
+
+/// Parses `url` if it is a LinkedIn profile (`/in/:account`) URL.
+fn profile_url(url: &str) -> Option<url::Url> {
+    let url = url::Url::parse(url).ok()?;
+    let linkedin = url
+        .host_str()
+        .is_some_and(|host| host == "linkedin.com" || host.ends_with(".linkedin.com"));
+    let account = url.path().strip_prefix("/in/")?.trim_end_matches('/');
+    (linkedin && !account.is_empty() && !account.contains('/')).then_some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_profile_urls_are_accepted() {
+        for url in [
+            "https://www.linkedin.com/in/someone",
+            "https://linkedin.com/in/someone/",
+        ] {
+            assert!(profile_url(url).is_some(), "{url}");
+        }
+        for url in [
+            "https://www.linkedin.com/company/acme",
+            "https://www.linkedin.com/search/results/people/?keywords=rust",
+            "https://www.linkedin.com/in/",
+            "https://www.linkedin.com/in/someone/details/skills",
+            "https://example.com/in/someone",
+            "https://notlinkedin.com/in/someone",
+            "someone",
+        ] {
+            assert!(profile_url(url).is_none(), "{url}");
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// This is free and unencumbered software released into the public domain.
+
+use asimov_linkup_module::{Client, Reaction, cli, error::WriteError};
+use asimov_module::SysexitsError::{self, *};
+use clientele::{
+    StandardOptions,
+    crates::clap::{self, Parser, Subcommand},
+};
+use std::{io::Write, path::PathBuf};
+
+#[cfg(not(feature = "std"))]
+fn main() {
+    unimplemented!("asimov-linkup-poster requires the 'std' feature")
+}
+
+/// ASIMOV Linkup Poster
+///
+/// Every performed action is appended to `~/.asimov/state/linkup/audit.jsonl`.
+#[derive(Debug, Parser)]
+#[command(name = "asimov-linkup-poster", long_about, after_long_help = cli::CONFIRMED_EXIT_STATUS)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    auth: cli::AuthOptions,
+
+    #[clap(flatten)]
+    credits: cli::CreditOptions,
+
+    /// Perform the action without asking for confirmation, as required
+    /// without a terminal.
+    #[arg(short = 'y', long)]
+    yes: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Publish a text post, read from stdin or a file.
+    Post {
+        /// Read the post text from a file instead of standard input.
+        #[arg(value_name = "FILE", short = 'f', long)]
+        file: Option<PathBuf>,
+
+        /// A link to share with the post.
+        #[arg(value_name = "URL", long)]
+        link: Option<String>,
+    },
+
+    /// Comment on a post, reading the comment from stdin or a file.
+    Comment {
+        /// Read the comment text from a file instead of standard input.
+        #[arg(value_name = "FILE", short = 'f', long)]
+        file: Option<PathBuf>,
+
+        /// The LinkedIn post URL.
+        url: String,
+    },
+
+    /// React to a post.
+    React {
+        /// The reaction: like, celebrate, support, love, insightful or funny.
+        #[arg(value_name = "REACTION", short = 'r', long, default_value = "like")]
+        reaction: Reaction,
+
+        /// The LinkedIn post URL.
+        url: String,
+    },
+}
+
+impl Command {
+    async fn perform(&self, client: &Client, text: &str) -> Result<serde_json::Value, WriteError> {
+        match self {
+            Command::Post { link, .. } => client.create_post(text, link.as_deref()).await,
+            Command::Comment { url, .. } => client.comment_post(url, text).await,
+            Command::React { url, reaction } => client.react_post(url, *reaction).await,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[tokio::main]
+async fn main() -> SysexitsError {
+    // Exit with the error's code rather than the generic failure code:
+    run().await.unwrap_or_else(|code| code)
+}
+
+#[cfg(feature = "std")]
+async fn run() -> Result<SysexitsError, SysexitsError> {
+    // Load environment variables from `.env`:
+    clientele::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    let Some(command) = options.command else {
+        return Ok(EX_OK);
+    };
+
+    let (action, target, text) = match &command {
+        Command::Post { file, link } => (
+            "create-post",
+            link.clone().unwrap_or_default(),
+            cli::read_text(file.as_deref())?,
+        ),
+        Command::Comment { file, url } => (
+            "comment-post",
+            url.clone(),
+            cli::read_text(file.as_deref())?,
+        ),
+        Command::React { reaction, url } => ("react-post", url.clone(), format!("{reaction:?}")),
+    };
+
+    if !options.yes {
+        let mut stderr = std::io::stderr().lock();
+        writeln!(&mut stderr, "{action} {target}\n{text}").unwrap();
+        drop(stderr);
+
+        cli::confirm("Perform this action?")?;
+    }
+
+    let manifest = cli::read_manifest()?;
+    let http_client = cli::http_client();
+    let mut client = cli::connect(&http_client, &manifest, &options.auth).await?;
+    client.credit_budget = options.credits.budget();
+
+    let result = command.perform(&client, &text).await;
+
+    cli::audit(action, &target, &result)?;
+
+    match result {
+        Ok(response) => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer(&mut stdout, &response).unwrap();
+            writeln!(&mut stdout).unwrap();
+        }
+        Err(e) => return Err(cli::write_error(e)),
+    }
+
+    Ok(EX_OK)
+}
@@ -0,0 +1,166 @@
+// This is free and unencumbered software released into the public domain.
+
+use asimov_linkup_module::{Client, MessageRecipient, SEND_MESSAGE_ENDPOINT, cli};
+use asimov_module::SysexitsError::{self, *};
+use clientele::{
+    StandardOptions,
+    crates::clap::{self, Parser},
+};
+use std::{io::Write, path::PathBuf};
+
+#[cfg(not(feature = "std"))]
+fn main() {
+    unimplemented!("asimov-linkup-sender requires the 'std' feature")
+}
+
+/// ASIMOV Linkup Sender
+///
+/// Every sent message is appended to `~/.asimov/state/linkup/audit.jsonl`.
+#[derive(Debug, Parser)]
+#[command(name = "asimov-linkup-sender", long_about, after_long_help = cli::CONFIRMED_EXIT_STATUS)]
+struct Options {
+    #[clap(flatten)]
+    flags: StandardOptions,
+
+    #[clap(flatten)]
+    auth: cli::AuthOptions,
+
+    #[clap(flatten)]
+    credits: cli::CreditOptions,
+
+    /// Read the message text from a file instead of standard input.
+    #[arg(value_name = "FILE", short = 'f', long)]
+    file: Option<PathBuf>,
+
+    /// Print the requests instead of sending them. Conversations are still
+    /// looked up in the inbox, which takes credits.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Send without asking for confirmation, as required without a terminal.
+    #[arg(short = 'y', long)]
+    yes: bool,
+
+    /// Profile (`/in/:account`) or conversation (`/messaging/thread/:id`) URLs.
+    urls: Vec<String>,
+}
+
+#[cfg(feature = "std")]
+#[tokio::main]
+async fn main() -> SysexitsError {
+    // Exit with the error's code rather than the generic failure code:
+    run().await.unwrap_or_else(|code| code)
+}
+
+#[cfg(feature = "std")]
+async fn run() -> Result<SysexitsError, SysexitsError> {
+    // Load environment variables from `.env`:
+    clientele::dotenv().ok();
+
+    // Expand wildcards and @argfiles:
+    let args = asimov_module::args_os()?;
+
+    // Parse command-line options:
+    let options = Options::parse_from(args);
+
+    // Handle the `--version` flag:
+    if options.flags.version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        return Ok(EX_OK);
+    }
+
+    // Handle the `--license` flag:
+    if options.flags.license {
+        print!("{}", include_str!("../../UNLICENSE"));
+        return Ok(EX_OK);
+    }
+
+    // Configure logging & tracing:
+    #[cfg(feature = "tracing")]
+    asimov_module::init_tracing_subscriber(&options.flags).expect("failed to initialize logging");
+
+    if options.urls.is_empty() {
+        return Ok(EX_OK);
+    }
+
+    let text = cli::read_text(options.file.as_deref())?;
+    let text = text.as_str();
+
+    // Reject unknown URLs before connecting or sending to any:
+    let mut recipients = Vec::new();
+    for url in &options.urls {
+        match MessageRecipient::parse(url) {
+            Ok(recipient) => recipients.push((url, recipient)),
+            Err(e) => {
+                tracing::error!("{e}");
+                return Err(EX_USAGE);
+            }
+        }
+    }
+
+    let mut stdout = std::io::stdout().lock();
+
+    if options.dry_run {
+        // Conversations are looked up in the inbox, which needs a client:
+        let mut client: Option<Client> = None;
+        for (url, recipient) in recipients {
+            let request = match (recipient.request(text), &client) {
+                (Some(request), _) => Ok(request),
+                (None, Some(client)) => client.message_request(&recipient, text).await,
+                (None, None) => {
+                    let client = client.insert(connect(&options).await?);
+                    client.message_request(&recipient, text).await
+                }
+            };
+            let request = request.map_err(cli::write_error)?;
+            let record = serde_json::json!({
+                "url": url,
+                "endpoint": SEND_MESSAGE_ENDPOINT,
+                "request": request,
+            });
+            serde_json::to_writer(&mut stdout, &record).unwrap();
+            writeln!(&mut stdout).unwrap();
+        }
+        return Ok(EX_OK);
+    }
+
+    if !options.yes {
+        let mut stderr = std::io::stderr().lock();
+        for url in &options.urls {
+            writeln!(&mut stderr, "send-message {url}").unwrap();
+        }
+        writeln!(&mut stderr, "{text}").unwrap();
+        drop(stderr);
+
+        let prompt = format!("Send this message to {} recipients?", options.urls.len());
+        cli::confirm(&prompt)?;
+    }
+
+    let client = connect(&options).await?;
+
+    for url in &options.urls {
+        let result = client.send_message(url, text).await;
+
+        cli::audit("send-message", url, &result)?;
+
+        match result {
+            Ok(response) => {
+                serde_json::to_writer(&mut stdout, &response).unwrap();
+                writeln!(&mut stdout).unwrap();
+            }
+            Err(e) => return Err(cli::write_error(e)),
+        }
+    }
+
+    Ok(EX_OK)
+}
+
+/// Connects to the Linkup API with the credit budget from the options.
+#[cfg(feature = "std")]
+async fn connect(options: &Options) -> Result<Client, SysexitsError> {
+    let manifest = cli::read_manifest()?;
+    let http_client = cli::http_client();
+    let mut client = cli::connect(&http_client, &manifest, &options.auth).await?;
+    client.credit_budget = options.credits.budget();
+    Ok(client)
+}
@@ -0,0 +1,323 @@
+// This is free and unencumbered software released into the public domain.
+
+use std::collections::BTreeMap;
+use std::string::String;
+use std::vec::Vec;
+
+/// A message template with `{{name}}` placeholders.
+///
+/// A placeholder may specify a fallback used when the variable is missing or
+/// empty, as in `{{first_name|there}}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable {
+        name: String,
+        fallback: Option<String>,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("unclosed placeholder at byte {0}")]
+    UnclosedPlaceholder(usize),
+    #[error("empty placeholder at byte {0}")]
+    EmptyPlaceholder(usize),
+    #[error("missing value for variable `{0}`")]
+    MissingVariable(String),
+}
+
+impl Template {
+    pub fn parse(input: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut rest = input;
+
+        while let Some(start) = rest.find("{{") {
+            let offset = input.len() - rest.len() + start;
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].into()));
+            }
+
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or(TemplateError::UnclosedPlaceholder(offset))?;
+
+            let (name, fallback) = match after[..end].split_once('|') {
+                Some((name, fallback)) => (name.trim(), Some(fallback.trim().into())),
+                None => (after[..end].trim(), None),
+            };
+            if name.is_empty() {
+                return Err(TemplateError::EmptyPlaceholder(offset));
+            }
+
+            parts.push(Part::Variable {
+                name: name.into(),
+                fallback,
+            });
+            rest = &after[end + 2..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.into()));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Returns the names of the variables used by the template.
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Variable { name, .. } => Some(name.as_str()),
+            Part::Text(_) => None,
+        })
+    }
+
+    pub fn render(&self, variables: &BTreeMap<String, String>) -> Result<String, TemplateError> {
+        let mut output = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => output.push_str(text),
+                Part::Variable { name, fallback } => {
+                    let value = variables
+                        .get(name)
+                        .map(|value| value.trim())
+                        .filter(|value| !value.is_empty())
+                        .or(fallback.as_deref())
+                        .ok_or_else(|| TemplateError::MissingVariable(name.clone()))?;
+                    output.push_str(value);
+                }
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+impl core::str::FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+/// Extracts the `first_name`, `last_name`, `full_name`, `company` and `title`
+/// template variables from a fetched profile.
+pub fn profile_variables(profile: &serde_json::Value) -> BTreeMap<String, String> {
+    fn first<'a>(profile: &'a serde_json::Value, pointers: &[&str]) -> Option<&'a str> {
+        pointers
+            .iter()
+            .filter_map(|pointer| profile.pointer(pointer)?.as_str())
+            .map(str::trim)
+            .find(|value| !value.is_empty())
+    }
+
+    let mut variables = BTreeMap::new();
+
+    let first_name = first(profile, &["/first_name", "/firstName"]);
+    let last_name = first(profile, &["/last_name", "/lastName"]);
+    let full_name = first(profile, &["/full_name", "/name"]);
+    let company = first(
+        profile,
+        &[
+            "/company",
+            "/current_company/name",
+            "/experience/0/company",
+            "/experiences/0/company",
+        ],
+    );
+    let title = first(
+        profile,
+        &[
+            "/title",
+            "/job_title",
+            "/experience/0/title",
+            "/experiences/0/title",
+            "/headline",
+        ],
+    );
+
+    let first_name = first_name.or_else(|| full_name?.split_whitespace().next());
+
+    for (name, value) in [
+        ("first_name", first_name),
+        ("last_name", last_name),
+        ("full_name", full_name),
+        ("company", company),
+        ("title", title),
+    ] {
+        if let Some(value) = value {
+            variables.insert(name.into(), value.into());
+        }
+    }
+
+    variables
+}
+
+/// Fills in the variables missing or blank in `variables` with the
+/// [`profile_variables`] of a fetched profile, so that non-empty values, e.g.
+/// from CSV columns, take precedence.
+pub fn merge_profile_variables(
+    variables: &mut BTreeMap<String, String>,
+    profile: &serde_json::Value,
+) {
+    for (name, value) in profile_variables(profile) {
+        let variable = variables.entry(name).or_default();
+        if variable.trim().is_empty() {
+            *variable = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::string::ToString;
+
+    fn variables(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn render(template: &str, pairs: &[(&str, &str)]) -> Result<String, TemplateError> {
+        Template::parse(template)?.render(&variables(pairs))
+    }
+
+    #[test]
+    fn renders_text_and_variables() {
+        let output = render(
+            "Hi {{ first_name }}, how is {{company}}?",
+            &[("first_name", "Ada"), ("company", "Analytical Engines")],
+        );
+        assert_eq!(output.unwrap(), "Hi Ada, how is Analytical Engines?");
+        assert_eq!(render("", &[]).unwrap(), "");
+        assert_eq!(render("No placeholders", &[]).unwrap(), "No placeholders");
+    }
+
+    #[test]
+    fn lists_variables() {
+        let template = Template::parse("{{a}} and {{b|x}}").unwrap();
+        assert_eq!(template.variables().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn uses_fallbacks_for_missing_and_blank_variables() {
+        assert_eq!(render("Hi {{name|there}}", &[]).unwrap(), "Hi there");
+        assert_eq!(
+            render("Hi {{name|there}}", &[("name", "  ")]).unwrap(),
+            "Hi there"
+        );
+        assert_eq!(
+            render("Hi {{name|there}}", &[("name", "Bo")]).unwrap(),
+            "Hi Bo"
+        );
+        assert_eq!(render("Hi{{name|}}!", &[]).unwrap(), "Hi!");
+    }
+
+    #[test]
+    fn fails_on_missing_variables() {
+        let error = render("Hi {{name}}", &[("other", "x")]).unwrap_err();
+        assert!(matches!(error, TemplateError::MissingVariable(name) if name == "name"));
+        let error = render("Hi {{name}}", &[("name", "")]).unwrap_err();
+        assert!(matches!(error, TemplateError::MissingVariable(_)));
+    }
+
+    #[test]
+    fn fails_on_unclosed_placeholders() {
+        let error = Template::parse("Hi {{name").unwrap_err();
+        assert!(matches!(error, TemplateError::UnclosedPlaceholder(3)));
+        let error = Template::parse("{{a}} {{b}").unwrap_err();
+        assert!(matches!(error, TemplateError::UnclosedPlaceholder(6)));
+    }
+
+    #[test]
+    fn fails_on_empty_placeholders() {
+        let error = Template::parse("Hi {{}}").unwrap_err();
+        assert!(matches!(error, TemplateError::EmptyPlaceholder(3)));
+        let error = Template::parse("{{|x}}").unwrap_err();
+        assert!(matches!(error, TemplateError::EmptyPlaceholder(0)));
+        let error = Template::parse("{{ | x}}").unwrap_err();
+        assert!(matches!(error, TemplateError::EmptyPlaceholder(0)));
+    }
+
+    #[test]
+    fn handles_non_ascii_text() {
+        let output = render(
+            "Grüß dich, {{name|Frëmde}} 👋 — {{city}}",
+            &[("city", "Zürich")],
+        );
+        assert_eq!(output.unwrap(), "Grüß dich, Frëmde 👋 — Zürich");
+        // Offsets are in bytes, `é` taking two:
+        let error = Template::parse("é {{x").unwrap_err();
+        assert!(matches!(error, TemplateError::UnclosedPlaceholder(3)));
+    }
+
+    #[test]
+    fn extracts_profile_variables() {
+        let profile = json!({
+            "first_name": "Ada",
+            "last_name": "Lovelace",
+            "full_name": "Ada Lovelace",
+            "current_company": { "name": "Analytical Engines" },
+            "experience": [{ "company": "Old Co", "title": "Mathematician" }],
+            "headline": "Poet of science",
+        });
+        assert_eq!(
+            profile_variables(&profile),
+            variables(&[
+                ("first_name", "Ada"),
+                ("last_name", "Lovelace"),
+                ("full_name", "Ada Lovelace"),
+                ("company", "Analytical Engines"),
+                ("title", "Mathematician"),
+            ])
+        );
+    }
+
+    #[test]
+    fn extracts_profile_variables_from_alternative_fields() {
+        let profile = json!({
+            "firstName": "  ",
+            "name": "Grace Brewster Hopper",
+            "experiences": [{ "company": "US Navy" }],
+            "headline": " Rear admiral ",
+        });
+        assert_eq!(
+            profile_variables(&profile),
+            variables(&[
+                ("first_name", "Grace"),
+                ("full_name", "Grace Brewster Hopper"),
+                ("company", "US Navy"),
+                ("title", "Rear admiral"),
+            ])
+        );
+        assert!(profile_variables(&json!({})).is_empty());
+        assert!(profile_variables(&json!([])).is_empty());
+    }
+
+    #[test]
+    fn merges_profile_variables_into_blank_ones() {
+        let profile = json!({ "first_name": "Ada", "last_name": "Lovelace", "title": "Countess" });
+        let mut merged = variables(&[("first_name", "Augusta"), ("last_name", " "), ("note", "x")]);
+        merge_profile_variables(&mut merged, &profile);
+        assert_eq!(
+            merged,
+            variables(&[
+                ("first_name", "Augusta"),
+                ("last_name", "Lovelace"),
+                ("note", "x"),
+                ("title", "Countess"),
+            ])
+        );
+    }
+}
@@ -0,0 +1,495 @@
+// This is free and unencumbered software released into the public domain.
+
+use asimov_module::secrecy::{ExposeSecret, SecretString};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng, rand_core::RngCore},
+};
+use jiff::{SignedDuration, Timestamp};
+use std::collections::BTreeMap;
+use std::format;
+use std::path::{Path, PathBuf};
+use std::string::{String, ToString};
+use std::sync::Mutex;
+use std::vec::Vec;
+
+/// Persistent storage for Linkup login tokens, keyed by account.
+///
+/// The account is typically the LinkedIn email address. Stores record the
+/// account along with the token and refuse to return a token saved for a
+/// different account.
+pub trait TokenStore: Send + Sync {
+    /// Returns the token record stored for the account, if any.
+    fn load(&self, account: &str) -> Result<Option<TokenRecord>, TokenStoreError>;
+
+    /// Stores the record under its account, replacing any previously stored
+    /// one.
+    fn save(&self, record: &TokenRecord) -> Result<(), TokenStoreError>;
+
+    /// Deletes the token stored for the account, if any.
+    fn delete(&self, account: &str) -> Result<(), TokenStoreError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TokenStoreError {
+    #[cfg(feature = "keyring")]
+    #[error("keyring error: {0}")]
+    Keyring(#[from] keyring::Error),
+    #[error("failed to access token file `{path}`: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to decrypt token file `{0}`")]
+    Decrypt(PathBuf),
+    #[error("invalid stored token: {0}")]
+    InvalidData(#[from] serde_json::Error),
+    #[error("stored token belongs to `{found}`, not `{expected}`")]
+    AccountMismatch { expected: String, found: String },
+}
+
+/// A stored login token and what is known about it.
+#[derive(Clone, Debug)]
+pub struct TokenRecord {
+    /// The account the token was issued for, the normalized email address.
+    pub account: String,
+    pub token: SecretString,
+    /// The country the token was requested for.
+    pub country: String,
+    /// When the token was issued, or `None` for tokens saved by versions
+    /// that didn't record it.
+    pub issued_at: Option<Timestamp>,
+    /// When the Linkup API last accepted the token, if ever checked.
+    pub last_validated_at: Option<Timestamp>,
+}
+
+impl TokenRecord {
+    /// Returns a record of a token issued to the account just now.
+    pub fn new(
+        account: impl Into<String>,
+        token: SecretString,
+        country: impl Into<String>,
+    ) -> Self {
+        Self {
+            account: account.into(),
+            token,
+            country: country.into(),
+            issued_at: Some(Timestamp::now()),
+            last_validated_at: None,
+        }
+    }
+
+    /// Returns how long ago the token was issued, if known.
+    pub fn age(&self) -> Option<SignedDuration> {
+        self.issued_at
+            .map(|issued_at| Timestamp::now().duration_since(issued_at))
+    }
+
+    fn encode(&self) -> Result<String, TokenStoreError> {
+        Ok(serde_json::to_string(&StoredToken {
+            account: self.account.clone(),
+            token: self.token.expose_secret().into(),
+            country: Some(self.country.clone()),
+            issued_at: self.issued_at,
+            last_validated_at: self.last_validated_at,
+        })?)
+    }
+
+    fn decode(account: &str, record: &str) -> Result<Self, TokenStoreError> {
+        let record: StoredToken = serde_json::from_str(record)?;
+        if record.account != account {
+            return Err(TokenStoreError::AccountMismatch {
+                expected: account.into(),
+                found: record.account,
+            });
+        }
+        Ok(Self {
+            account: record.account,
+            token: record.token.into(),
+            country: record
+                .country
+                .unwrap_or_else(|| crate::client::COUNTRY.into()),
+            issued_at: record.issued_at,
+            last_validated_at: record.last_validated_at,
+        })
+    }
+}
+
+/// The serialized form of a [`TokenRecord`]. Records saved by earlier
+/// versions have only the account and token.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredToken {
+    account: String,
+    token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    issued_at: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_validated_at: Option<Timestamp>,
+}
+
+/// Stores the token in the operating system's keyring (Keychain, Secret
+/// Service, Windows Credential Manager).
+///
+/// Each account's token is kept in a separate entry of the given service.
+#[cfg(feature = "keyring")]
+#[derive(Debug)]
+pub struct KeyringTokenStore {
+    service: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringTokenStore {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+
+    fn entry(&self, account: &str) -> Result<keyring::Entry, TokenStoreError> {
+        Ok(keyring::Entry::new(&self.service, account)?)
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl TokenStore for KeyringTokenStore {
+    fn load(&self, account: &str) -> Result<Option<TokenRecord>, TokenStoreError> {
+        match self.entry(account)?.get_password() {
+            Ok(record) if record.is_empty() => Ok(None),
+            Ok(record) => TokenRecord::decode(account, &record).map(Some),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, record: &TokenRecord) -> Result<(), TokenStoreError> {
+        Ok(self
+            .entry(&record.account)?
+            .set_password(&record.encode()?)?)
+    }
+
+    fn delete(&self, account: &str) -> Result<(), TokenStoreError> {
+        match self.entry(account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Stores each account's token in a file encrypted with ChaCha20-Poly1305,
+/// for hosts without a keyring such as containers and CI runners.
+///
+/// The encryption key is either derived from a passphrase with Argon2, or
+/// randomly generated and kept in a separate key file readable only by the
+/// current user.
+#[derive(Debug)]
+pub struct FileTokenStore {
+    dir: PathBuf,
+    key: FileKey,
+}
+
+#[derive(Debug)]
+enum FileKey {
+    Passphrase(SecretString),
+    KeyFile(PathBuf),
+}
+
+const FILE_MAGIC: &[u8; 4] = b"LKT1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+impl FileTokenStore {
+    /// Keeps token files in `dir`, encrypted with a key derived from
+    /// `passphrase`.
+    pub fn with_passphrase(dir: impl Into<PathBuf>, passphrase: SecretString) -> Self {
+        Self {
+            dir: dir.into(),
+            key: FileKey::Passphrase(passphrase),
+        }
+    }
+
+    /// Keeps token files in `dir`, encrypted with a random key stored at
+    /// `key_path`, which is created on first use.
+    pub fn with_key_file(dir: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            key: FileKey::KeyFile(key_path.into()),
+        }
+    }
+
+    /// Returns the path of the account's token file.
+    pub fn path(&self, account: &str) -> PathBuf {
+        let name: String = account
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '@' | '.' | '+' | '-' | '_' => c,
+                _ => '_',
+            })
+            .collect();
+        self.dir.join(format!("{name}.enc"))
+    }
+
+    fn key(&self, path: &Path, salt: &[u8]) -> Result<Key, TokenStoreError> {
+        let mut key = Key::default();
+        match &self.key {
+            FileKey::Passphrase(passphrase) => {
+                argon2::Argon2::default()
+                    .hash_password_into(passphrase.expose_secret().as_bytes(), salt, &mut key)
+                    .map_err(|_| TokenStoreError::Decrypt(path.into()))?;
+            }
+            FileKey::KeyFile(key_path) => match std::fs::read(key_path) {
+                Ok(bytes) if bytes.len() == key.len() => key.copy_from_slice(&bytes),
+                Ok(_) => return Err(TokenStoreError::Decrypt(key_path.clone())),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    OsRng.fill_bytes(&mut key);
+                    write_private(key_path, &key)?;
+                }
+                Err(source) => {
+                    return Err(TokenStoreError::Io {
+                        path: key_path.clone(),
+                        source,
+                    });
+                }
+            },
+        }
+        Ok(key)
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self, account: &str) -> Result<Option<TokenRecord>, TokenStoreError> {
+        let path = self.path(account);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(TokenStoreError::Io { path, source });
+            }
+        };
+
+        let invalid = || TokenStoreError::Decrypt(path.clone());
+
+        let rest = bytes.strip_prefix(FILE_MAGIC).ok_or_else(invalid)?;
+        if rest.len() < SALT_LEN + NONCE_LEN {
+            return Err(invalid());
+        }
+        let (salt, rest) = rest.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let cipher = ChaCha20Poly1305::new(&self.key(&path, salt)?);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+        let record = String::from_utf8(plaintext).map_err(|_| invalid())?;
+
+        TokenRecord::decode(account, &record).map(Some)
+    }
+
+    fn save(&self, record: &TokenRecord) -> Result<(), TokenStoreError> {
+        let path = self.path(&record.account);
+        let record = record.encode()?;
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let cipher = ChaCha20Poly1305::new(&self.key(&path, &salt)?);
+        let ciphertext = cipher
+            .encrypt(&nonce, record.as_bytes())
+            .map_err(|_| TokenStoreError::Decrypt(path.clone()))?;
+
+        let mut bytes =
+            Vec::with_capacity(FILE_MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+        bytes.extend_from_slice(FILE_MAGIC);
+        bytes.extend_from_slice(&salt);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+
+        write_private(&path, &bytes)
+    }
+
+    fn delete(&self, account: &str) -> Result<(), TokenStoreError> {
+        let path = self.path(account);
+        match std::fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(source) => Err(TokenStoreError::Io { path, source }),
+        }
+    }
+}
+
+/// Writes a file readable and writable only by the current user.
+fn write_private(path: &Path, bytes: &[u8]) -> Result<(), TokenStoreError> {
+    use std::io::Write;
+
+    let write = || -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        options.open(path)?.write_all(bytes)
+    };
+
+    write().map_err(|source| TokenStoreError::Io {
+        path: path.into(),
+        source,
+    })
+}
+
+/// Keeps tokens in memory only, for the lifetime of the process.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<BTreeMap<String, TokenRecord>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self, account: &str) -> Result<Option<TokenRecord>, TokenStoreError> {
+        Ok(self.tokens.lock().unwrap().get(account).cloned())
+    }
+
+    fn save(&self, record: &TokenRecord) -> Result<(), TokenStoreError> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(record.account.to_string(), record.clone());
+        Ok(())
+    }
+
+    fn delete(&self, account: &str) -> Result<(), TokenStoreError> {
+        self.tokens.lock().unwrap().remove(account);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = "someone@example.com";
+
+    /// Returns an empty directory for the test `name` to keep files in.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "asimov-linkup-token-store-{}-{name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn record() -> TokenRecord {
+        let mut record = TokenRecord::new(ACCOUNT, SecretString::from("secret-token"), "US");
+        record.last_validated_at = record.issued_at;
+        record
+    }
+
+    fn assert_same(loaded: &TokenRecord, saved: &TokenRecord) {
+        assert_eq!(loaded.account, saved.account);
+        assert_eq!(loaded.token.expose_secret(), saved.token.expose_secret());
+        assert_eq!(loaded.country, saved.country);
+        assert_eq!(loaded.issued_at, saved.issued_at);
+        assert_eq!(loaded.last_validated_at, saved.last_validated_at);
+    }
+
+    #[test]
+    fn passphrase_file_round_trip() {
+        let dir = temp_dir("passphrase");
+        let store = FileTokenStore::with_passphrase(&dir, SecretString::from("correct horse"));
+        assert!(store.load(ACCOUNT).unwrap().is_none());
+
+        let saved = record();
+        store.save(&saved).unwrap();
+        let bytes = std::fs::read(store.path(ACCOUNT)).unwrap();
+        assert!(bytes.starts_with(FILE_MAGIC));
+        assert!(!bytes.windows(12).any(|window| window == b"secret-token"));
+        assert_same(&store.load(ACCOUNT).unwrap().unwrap(), &saved);
+
+        store.delete(ACCOUNT).unwrap();
+        assert!(store.load(ACCOUNT).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn key_file_round_trip() {
+        let dir = temp_dir("key-file");
+        let key_path = dir.join("key");
+        let store = FileTokenStore::with_key_file(&dir, &key_path);
+
+        let saved = record();
+        store.save(&saved).unwrap();
+        assert_eq!(
+            std::fs::read(&key_path).unwrap().len(),
+            Key::default().len()
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let reopened = FileTokenStore::with_key_file(&dir, &key_path);
+        assert_same(&reopened.load(ACCOUNT).unwrap().unwrap(), &saved);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn wrong_passphrase_fails_to_decrypt() {
+        let dir = temp_dir("wrong-passphrase");
+        let store = FileTokenStore::with_passphrase(&dir, SecretString::from("correct horse"));
+        store.save(&record()).unwrap();
+
+        let wrong = FileTokenStore::with_passphrase(&dir, SecretString::from("battery staple"));
+        match wrong.load(ACCOUNT) {
+            Err(TokenStoreError::Decrypt(path)) => assert_eq!(path, store.path(ACCOUNT)),
+            other => panic!("expected a decryption error, got {other:?}"),
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn corrupted_file_fails_to_decrypt() {
+        let dir = temp_dir("corrupted");
+        let store = FileTokenStore::with_passphrase(&dir, SecretString::from("correct horse"));
+        store.save(&record()).unwrap();
+
+        let path = store.path(ACCOUNT);
+        let mut bytes = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            store.load(ACCOUNT),
+            Err(TokenStoreError::Decrypt(_))
+        ));
+
+        std::fs::write(&path, b"LKT1").unwrap();
+        assert!(matches!(
+            store.load(ACCOUNT),
+            Err(TokenStoreError::Decrypt(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn memory_round_trip() {
+        let store = MemoryTokenStore::new();
+        let saved = record();
+        store.save(&saved).unwrap();
+        assert_same(&store.load(ACCOUNT).unwrap().unwrap(), &saved);
+        store.delete(ACCOUNT).unwrap();
+        assert!(store.load(ACCOUNT).unwrap().is_none());
+    }
+}